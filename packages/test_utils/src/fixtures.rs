@@ -8,6 +8,12 @@ pub enum Fixture {
     ExampleDotWithLinkAdded,
     ExampleDotWithMultiLink,
     ExampleDotWithDirectory,
+    ExampleDotWithNestedLink,
+    ExampleDotWithConditionalLinks,
+    ExampleDotWithProfiles,
+    ExampleDotWithTemplate,
+    ExampleDotWithCopy,
+    ExampleDotWithHardlink,
     ConflictingDot,
 }
 
@@ -23,6 +29,12 @@ impl Fixture {
             Self::ExampleDotWithDirectory => "example_dot_with_directory",
             Self::ExampleDotWithLinkAdded => "example_dot",
             Self::ExampleDotWithMultiLink => "example_dot",
+            Self::ExampleDotWithNestedLink => "example_dot",
+            Self::ExampleDotWithConditionalLinks => "example_dot",
+            Self::ExampleDotWithProfiles => "example_dot",
+            Self::ExampleDotWithTemplate => "example_dot",
+            Self::ExampleDotWithCopy => "example_dot",
+            Self::ExampleDotWithHardlink => "example_dot",
             Self::ExampleDotWithUnlinkedFile => "example_dot",
             Self::ExampleDot => "example_dot",
             Self::ConflictingDot => "conflicting_dot",
@@ -38,6 +50,22 @@ impl Fixture {
             Self::ExampleDotWithMultiLink => {
                 Self::templates_root().join("example_dot_with_multi_link")
             }
+            Self::ExampleDotWithNestedLink => {
+                Self::templates_root().join("example_dot_with_nested_link")
+            }
+            Self::ExampleDotWithConditionalLinks => {
+                Self::templates_root().join("example_dot_with_conditional_links")
+            }
+            Self::ExampleDotWithProfiles => {
+                Self::templates_root().join("example_dot_with_profiles")
+            }
+            Self::ExampleDotWithTemplate => {
+                Self::templates_root().join("example_dot_with_template")
+            }
+            Self::ExampleDotWithCopy => Self::templates_root().join("example_dot_with_copy"),
+            Self::ExampleDotWithHardlink => {
+                Self::templates_root().join("example_dot_with_hardlink")
+            }
             Self::ExampleDotWithUnlinkedFile => {
                 Self::templates_root().join("example_dot_with_unlinked_file")
             }
@@ -53,7 +53,7 @@ where
                                 write!(line, "{} ", sign_style.apply(sign)).ok();
 
                                 for &(emphasis, value) in change.values() {
-                                    let mut value_style = style.clone();
+                                    let mut value_style = style;
 
                                     if emphasis {
                                         value_style = value_style + styles::EMPHASIS;
@@ -52,7 +52,7 @@ impl TestManager {
     }
 
     /** Creates a copy of the given fixture in the test directory */
-    fn setup_fixture(&self, fixture: &Fixture) -> Result<Utf8PathBuf> {
+    pub fn setup_fixture(&self, fixture: &Fixture) -> Result<Utf8PathBuf> {
         let fixture_src = fixture.template_path();
         let fixture_dest = self.fixture_dir(fixture);
 
@@ -84,6 +84,15 @@ impl TestManager {
         Ok(())
     }
 
+    /** Replaces the contents of the given fixture's git repo with another fixture and commits it */
+    pub fn update_fixture(&self, fixture1: &Fixture, fixture2: &Fixture) -> Result<()> {
+        let path = self.fixture_dir(fixture1);
+        empty_git_directory(&path)?;
+        copy_dir(fixture2.template_path(), &path)?;
+        git::commit_all(&path, "update fixture")?;
+        Ok(())
+    }
+
     pub fn remove_dot(&self, fixture: &Fixture) -> Result<()> {
         let path = self.expected_dot_path(fixture);
         fs::remove_dir_all(&path)?;
@@ -91,16 +100,42 @@ impl TestManager {
     }
 
     pub fn cmd(&self, bin: &'static str) -> Result<Command> {
-        let mut cmd = Command::new(&bin);
+        let mut cmd = Command::new(bin);
         cmd.env("HOME", self.home_dir());
+        /* Make sure every path is resolved from HOME unless a test says otherwise */
+        cmd.env_remove("DOTS_HOME")
+            .env_remove("DOTS_FOOTPRINT")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("DOTS_CONFIG")
+            .env_remove("XDG_CONFIG_HOME");
         Ok(cmd)
     }
 
+    /**
+     * Reads the footprint, leaving out when each link was installed and by which version of dots
+     * so that it can be compared against the footprints in tests/footprints
+     */
     pub fn read_footprint(&self) -> Result<String> {
         let path = self.footprint_path();
         let mut file = fs::File::open(path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
+        let contents = contents
+            .split_inclusive('\n')
+            .filter(|line| {
+                !line.starts_with("installed = ") && !line.starts_with("dots_version = ")
+            })
+            .collect();
+        Ok(contents)
+    }
+
+    /** The path of the cli's config file */
+    pub fn config_path(&self) -> Utf8PathBuf {
+        self.home_dir().join(".config/dots/config.toml")
+    }
+
+    pub fn read_lockfile(&self) -> Result<String> {
+        let contents = fs::read_to_string(self.dots_dir().join("dots.lock"))?;
         Ok(contents)
     }
 
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use camino::{Utf8Path, Utf8PathBuf};
 use dirs::home_dir;
-use std::{fs, io, os};
+use std::{fs, io, io::Write, os};
 use walkdir::WalkDir;
 
 pub fn copy_dir<S, D>(source: S, destination: D) -> Result<(), io::Error>
@@ -34,6 +34,89 @@ where
     Ok(())
 }
 
+/// Recursively copies a directory, leaving out any entries with one of the given names
+pub fn copy_dir_excluding<S, D>(source: S, destination: D, excluded: &[&str]) -> io::Result<()>
+where
+    S: AsRef<Utf8Path>,
+    D: AsRef<Utf8Path>,
+{
+    let source = source.as_ref();
+    let destination = destination.as_ref();
+
+    let entries = WalkDir::new(source).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        !excluded.contains(&name.as_ref())
+    });
+
+    for entry in entries {
+        let entry = entry?;
+        let from = Utf8Path::from_path(entry.path())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Path is not utf8"))?;
+        let to = destination.join(from.strip_prefix(source).unwrap());
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&to)?;
+        } else {
+            fs::copy(from, &to)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves a file, symlink or directory to the given path. When the destination is on a different
+/// filesystem, where a rename isn't possible, it's copied over and the original is removed.
+pub fn move_path<F, T>(from: F, to: T) -> io::Result<()>
+where
+    F: AsRef<Utf8Path>,
+    T: AsRef<Utf8Path>,
+{
+    let from = from.as_ref();
+    let to = to.as_ref();
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(err) = copy_all(from, to) {
+                remove_all(to).ok();
+                return Err(err);
+            }
+            remove_all(from)
+        }
+        result => result,
+    }
+}
+
+/// Copies a file, symlink or directory, keeping symlinks as symlinks
+fn copy_all(from: &Utf8Path, to: &Utf8Path) -> io::Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let path = Utf8Path::from_path(entry.path())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Path is not utf8"))?;
+        let relative = path.strip_prefix(from).unwrap();
+        let target = match relative.as_str() {
+            "" => to.to_path_buf(),
+            _ => to.join(relative),
+        };
+
+        let file_type = entry.file_type();
+        if file_type.is_symlink() {
+            os::unix::fs::symlink(fs::read_link(path)?, &target)?;
+        } else if file_type.is_dir() {
+            fs::create_dir(&target)?;
+            fs::set_permissions(&target, entry.metadata()?.permissions())?;
+        } else {
+            fs::copy(path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes a file, symlink or directory
+fn remove_all(path: &Utf8Path) -> io::Result<()> {
+    match path.symlink_metadata()?.is_dir() {
+        true => fs::remove_dir_all(path),
+        false => fs::remove_file(path),
+    }
+}
+
 pub fn current_dir() -> Utf8PathBuf {
     let current_dir = std::env::current_dir().expect("Unable to get current directory");
     Utf8PathBuf::from_path_buf(current_dir).expect("Unable to parse current directory as utf8")
@@ -44,6 +127,23 @@ pub fn home() -> Utf8PathBuf {
     Utf8PathBuf::from_path_buf(home).expect("Unable to parse home directory as utf8")
 }
 
+/// Converts the given path to an absolute path without following any symlinks. Paths starting
+/// with `~/` are treated as relative to the home directory and all other relative paths are
+/// treated as relative to the current directory.
+pub fn absolute<P>(path: P) -> Utf8PathBuf
+where
+    P: AsRef<Utf8Path>,
+{
+    let path = path.as_ref();
+    if let Ok(relative) = path.strip_prefix("~") {
+        home().join(relative)
+    } else if path.is_relative() {
+        current_dir().join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
 pub fn clean(path: &Utf8Path) {
     if path.exists() {
         fs::remove_dir_all(path).ok();
@@ -80,6 +180,48 @@ where
     Ok(())
 }
 
+/// Hashes the contents of the given file. This is only meant for detecting changes to a file, not
+/// for anything security related.
+pub fn hash_file<P>(path: P) -> Result<String, io::Error>
+where
+    P: AsRef<Utf8Path>,
+{
+    Ok(hash(&fs::read(path.as_ref())?))
+}
+
+/// A 64-bit FNV-1a hash of the given bytes, formatted as hex
+pub fn hash(bytes: &[u8]) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let hash = bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}")
+}
+
+/// Writes the given contents to a temporary file next to the given path and renames it into place,
+/// so the file is never left half written if the process is interrupted.
+pub fn write_atomic<P, C>(path: P, contents: C) -> Result<(), io::Error>
+where
+    P: AsRef<Utf8Path>,
+    C: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let name = path.file_name().unwrap_or_default();
+    let temp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+
+    let result = fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    if let Err(err) = result.and_then(|_| fs::rename(&temp, path)) {
+        fs::remove_file(&temp).ok();
+        return Err(err);
+    }
+    Ok(())
+}
+
 pub fn canonicalize<P>(path: P) -> Result<Utf8PathBuf, io::Error>
 where
     P: AsRef<Utf8Path>,
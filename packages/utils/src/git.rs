@@ -114,12 +114,259 @@ where
     Ok(())
 }
 
-pub fn get_origin() -> Result<String, GitError> {
+/// Clones the given branch or tag of a repo
+pub fn clone_ref<P>(url: &str, dest: P, name: &str) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    map_result(
+        Command::new("git")
+            .arg("clone")
+            .arg(url)
+            .arg(dest.as_ref())
+            .arg("--branch")
+            .arg(name)
+            .arg("--depth=1")
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+/// Clones a repo along with its entire history
+pub fn clone_full<P>(url: &str, dest: P) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    map_result(
+        Command::new("git")
+            .arg("clone")
+            .arg(url)
+            .arg(dest.as_ref())
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+/// Checks out the given commit, leaving the repo with a detached HEAD
+pub fn checkout<P>(dir: P, rev: &str) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    map_result(
+        Command::new("git")
+            .arg("checkout")
+            .arg("--detach")
+            .arg(rev)
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+/// Checks out the given local branch, creating it from origin if it doesn't exist yet. Since
+/// single-branch clones only track their default branch, the branch is added to origin's tracked
+/// branches and fetched before it's created.
+pub fn switch<P>(dir: P, branch: &str) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let dir = dir.as_ref();
+
+    if rev_parse(dir, &format!("refs/heads/{branch}")).is_err() {
+        map_result(
+            Command::new("git")
+                .arg("remote")
+                .arg("set-branches")
+                .arg("--add")
+                .arg("origin")
+                .arg(branch)
+                .current_dir(dir)
+                .output(),
+        )?;
+
+        map_result(
+            Command::new("git")
+                .arg("fetch")
+                .arg("origin")
+                .arg(branch)
+                .current_dir(dir)
+                .output(),
+        )?;
+    }
+
+    map_result(
+        Command::new("git")
+            .arg("checkout")
+            .arg(branch)
+            .current_dir(dir)
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+/// Returns the name of the branch that origin's HEAD points to (e.g. "main")
+pub fn default_branch<P>(dir: P) -> Result<String, GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let output = map_result(
+        Command::new("git")
+            .arg("symbolic-ref")
+            .arg("--short")
+            .arg("refs/remotes/origin/HEAD")
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    let string = String::from_utf8(output.stdout).expect("unable to convert branch output to utf8");
+    let branch = string.trim();
+
+    Ok(branch.strip_prefix("origin/").unwrap_or(branch).to_string())
+}
+
+/// Whether the first rev is an ancestor of the second, or the same commit
+pub fn is_ancestor<P>(dir: P, ancestor: &str, descendant: &str) -> Result<bool, GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let result = map_result(
+        Command::new("git")
+            .arg("merge-base")
+            .arg("--is-ancestor")
+            .arg(ancestor)
+            .arg(descendant)
+            .current_dir(dir.as_ref())
+            .output(),
+    );
+
+    match result {
+        Ok(_) => Ok(true),
+        Err(GitError {
+            kind: GitErrorKind::Command(output),
+        }) if output.status.code() == Some(1) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+pub fn tag<P>(dir: P, name: &str) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    map_result(
+        Command::new("git")
+            .arg("tag")
+            .arg(name)
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+/// Returns the name of the current branch or "HEAD" if the repo is in a detached state
+pub fn get_branch<P>(dir: P) -> Result<String, GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let output = map_result(
+        Command::new("git")
+            .arg("rev-parse")
+            .arg("--abbrev-ref")
+            .arg("HEAD")
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    let string = String::from_utf8(output.stdout).expect("unable to convert branch output to utf8");
+
+    Ok(string.trim().to_string())
+}
+
+/// Returns the full SHA of the commit that the given rev points to
+pub fn rev_parse<P>(dir: P, rev: &str) -> Result<String, GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let output = map_result(
+        Command::new("git")
+            .arg("rev-parse")
+            .arg("--verify")
+            .arg(format!("{rev}^{{commit}}"))
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    let string = String::from_utf8(output.stdout).expect("unable to convert rev output to utf8");
+
+    Ok(string.trim().to_string())
+}
+
+/// Whether the repo was cloned with a limited history
+pub fn is_shallow<P>(dir: P) -> Result<bool, GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let output = map_result(
+        Command::new("git")
+            .arg("rev-parse")
+            .arg("--is-shallow-repository")
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    let string = String::from_utf8(output.stdout).expect("unable to convert rev output to utf8");
+
+    Ok(string.trim() == "true")
+}
+
+/// Fetches all commits from origin, including the full history of shallow clones
+pub fn fetch<P>(dir: P) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    let dir = dir.as_ref();
+    let mut command = Command::new("git");
+    command.arg("fetch").arg("origin").current_dir(dir);
+
+    if is_shallow(dir)? {
+        command.arg("--unshallow");
+    }
+
+    map_result(command.output())?;
+
+    Ok(())
+}
+
+/// Fetches new commits from the upstream branch and fast-forwards the current checkout
+pub fn pull<P>(dir: P) -> Result<(), GitError>
+where
+    P: AsRef<Utf8Path>,
+{
+    map_result(
+        Command::new("git")
+            .arg("pull")
+            .arg("--ff-only")
+            .current_dir(dir.as_ref())
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+pub fn get_origin<P>(dir: P) -> Result<String, GitError>
+where
+    P: AsRef<Utf8Path>,
+{
     let output = map_result(
         Command::new("git")
             .arg("remote")
             .arg("get-url")
             .arg("origin")
+            .current_dir(dir.as_ref())
             .output(),
     )?;
 
@@ -128,6 +375,22 @@ pub fn get_origin() -> Result<String, GitError> {
     Ok(string.trim().to_string())
 }
 
+/// Whether git can be found on the PATH
+pub fn is_installed() -> bool {
+    Command::new("git")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Whether the given directory is the root of a git checkout
+pub fn is_repo<P>(dir: P) -> bool
+where
+    P: AsRef<Utf8Path>,
+{
+    dir.as_ref().join(".git").exists()
+}
+
 pub fn get_status(dir: &Utf8Path) -> Result<String, GitError> {
     let output = map_result(
         Command::new("git")
@@ -54,6 +54,12 @@ pub struct Style {
     strikethrough: bool,
 }
 
+impl Default for Style {
+    fn default() -> Self {
+        Style::new()
+    }
+}
+
 impl Style {
     pub fn detect_color_support() {
         if supports_color::on(supports_color::Stream::Stdout).is_none() {
@@ -61,6 +67,15 @@ impl Style {
         }
     }
 
+    /** Turns colors on or off regardless of what the terminal supports */
+    pub fn set_color_support(enabled: bool) {
+        if enabled {
+            yansi::Paint::enable();
+        } else {
+            yansi::Paint::disable();
+        }
+    }
+
     pub const fn new() -> Style {
         Style { ..DEFAULT }
     }
@@ -1,127 +1,1101 @@
-use std::process;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::fs;
 
+use utils::fs::{absolute, hash, hash_file};
 use utils::git;
 use utils::text::indent;
 
-use crate::dots::{self, Environment};
+use crate::config::{display_value, Config, ConfigFile};
+use crate::doctor;
+use crate::dot_package::insert_link;
+use crate::dots::{self, BrokenDot, BrokenDotOutput, Dot, Environment};
+use crate::error::{DotsError, Result};
+use crate::footprint::{Footprint, LinkRecord};
 use crate::fs_manager::FSManager;
-use crate::plan::Plan;
+use crate::local::LocalMode;
+use crate::lockfile::Lockfile;
+use crate::output::{Document, Format};
+use crate::pin::{Pin, PinOutput, Pins};
+use crate::plan::actions::PlanFile;
+use crate::plan::confirm::Prompt;
+use crate::plan::journal::Journal;
+use crate::plan::links::{Link, LinkMode};
+use crate::plan::plan::PlanOutput;
+use crate::plan::{self, Plan};
+use crate::root_lock::RootLock;
 
 mod styles {
     use utils::stylize::Style;
 
     pub const HEADER: Style = Style::new().bold();
+    pub const WARN: Style = Style::new().yellow();
 }
 
-pub fn add(url: &str, overwrite: bool) {
-    let env = Environment::new();
-    dots::add(url, overwrite, &env)
+/**
+ * Locks the dots root for the rest of the command. An install that was interrupted before it could
+ * finish is rolled back first, so every command starts from a consistent footprint.
+ */
+fn lock_root(env: &Environment) -> Result<RootLock> {
+    let lock = RootLock::acquire(env)?;
+    let mut fs_manager = FSManager::init_unchecked(env);
+    Journal::recover(&mut fs_manager)
+        .map_err(|err| DotsError::footprint("Unable to roll back the last install:", err))?;
+    Ok(lock)
 }
 
-pub fn install(repo: &Option<String>, overwrite: bool, force: bool, dry: bool) {
-    let env = Environment::new();
-    if let Some(url) = repo {
-        dots::add(url, overwrite, &env);
-    };
-    let dots = dots::find_all(&env);
+/**
+ * Makes the given changes under a [`Journal`], keeping them and saving the footprint only if they
+ * all succeed. Otherwise every change is rolled back, leaving the fs & footprint as they were.
+ */
+fn with_journal<T>(
+    fs_manager: &mut FSManager,
+    changes: impl FnOnce(&mut Journal, &mut FSManager) -> Result<T>,
+) -> Result<T> {
+    let mut journal = Journal::begin(fs_manager)
+        .map_err(|err| DotsError::footprint("Unable to start the journal:", err))?;
+    let result = changes(&mut journal, fs_manager).and_then(|value| {
+        fs_manager
+            .save()
+            .map_err(|err| DotsError::footprint("Unable to save the footprint:", err))?;
+        Ok(value)
+    });
 
-    let mut plan = Plan::new(force);
+    match result {
+        Ok(value) => {
+            journal
+                .commit()
+                .map_err(|err| DotsError::footprint("Unable to commit the journal:", err))?;
+            Ok(value)
+        }
+        Err(err) => {
+            if let Err(rollback_err) = journal.rollback(fs_manager) {
+                error!(
+                    "Unable to roll back all changes, the next command will retry:\n{rollback_err}"
+                );
+            }
+            Err(err)
+        }
+    }
+}
 
-    let mut fs_manager = FSManager::init(&env);
-    plan.clean(&env, &mut fs_manager, &dots)
-        .unwrap_or_else(|err| {
-            error!("failed to clean current install:");
-            error!("{}", err);
-            process::exit(1);
-        });
+pub fn add(
+    env: &Environment,
+    url: &str,
+    overwrite: bool,
+    pin: Option<Pin>,
+    local: Option<LocalMode>,
+) -> Result<()> {
+    let _lock = lock_root(env)?;
+    dots::add(url, overwrite, pin, local, env)?;
+    Ok(())
+}
+
+/**
+ * Links the given dots, or every installed dot if none are given. Anything that isn't the name of
+ * an installed dot is added as a repo first.
+ */
+#[allow(clippy::too_many_arguments)]
+pub fn install(
+    env: &mut Environment,
+    names: &[String],
+    overwrite: bool,
+    pin: Option<Pin>,
+    locked: bool,
+    profile: Option<Option<String>>,
+    force: Option<bool>,
+    dry: bool,
+    diff: bool,
+    save_plan: &Option<String>,
+    format: Format,
+) -> Result<()> {
+    let _lock = lock_root(env)?;
+    if let Some(profile) = profile {
+        env.set_profile(profile);
+    }
+    let mut only = vec![];
+    if !names.is_empty() {
+        let (_, broken) = dots::load_all(env)?;
+        for name in names {
+            if let Some(dot) = broken.iter().find(|dot| &dot.name == name) {
+                return Err(DotsError::Invalid(format!(
+                    "Unable to install {name} until its Dot.toml is fixed:\n{}",
+                    dot.error
+                )));
+            }
+            match dots::find(name, env)? {
+                Some(dot) => only.push(dot.package.name),
+                None => only.push(dots::add(name, overwrite, pin.clone(), None, env)?),
+            }
+        }
+    }
+    let only = (!only.is_empty()).then_some(only);
+    let force = force.or(env.config().install.force).unwrap_or(false);
+    if locked {
+        let lockfile = Lockfile::read(env)
+            .map_err(|err| DotsError::parse("Unable to install the locked dots:", err))?;
+        for locked_dot in &lockfile.dots {
+            match dry {
+                true => dots::preview_locked(locked_dot, env)?,
+                false => dots::checkout_locked(locked_dot, env)?,
+            }
+        }
+    }
+    install_all(env, only, force, dry, diff, save_plan, format)
+}
+
+/** Plans and links every installed dot, or only the given dots */
+fn install_all(
+    env: &Environment,
+    only: Option<Vec<String>>,
+    force: bool,
+    dry: bool,
+    diff: bool,
+    save_plan: &Option<String>,
+    format: Format,
+) -> Result<()> {
+    let (mut dots, mut broken) = dots::load_all(env)?;
+    if let Some(names) = &only {
+        broken.retain(|dot| names.contains(&dot.name));
+    }
+
+    let mut save_profile = true;
+    if let Some(name) = env.profile() {
+        info!("Installing profile {name}");
+        if !dots
+            .iter()
+            .any(|dot| dot.profiles.iter().any(|p| p == name))
+        {
+            warn!("None of the installed dots define a profile named {name}, linking everything");
+            save_profile = false;
+        }
+    }
+
+    let broken_names: Vec<String> = broken.iter().map(|dot| dot.name.clone()).collect();
+    let mut plan = Plan::new(force)
+        .with_format(format)
+        .with_broken(broken)
+        .with_diff(diff);
+    if let Some(names) = only {
+        plan = plan.with_only(names);
+    }
+    if !force && !dry && format == Format::Text && Prompt::is_available() {
+        plan = plan.with_prompt(Prompt::stdin());
+    }
+
+    let mut fs_manager = FSManager::init(env)?;
+    with_journal(&mut fs_manager, |journal, fs_manager| {
+        plan.clean(env, journal, fs_manager, &mut dots)
+            .map_err(|err| DotsError::footprint("failed to clean current install:", err))?;
+
+        /* Validate whether the plan passes or fails */
+        let result = plan.validate(dots, fs_manager.footprint());
+        if format == Format::Json {
+            let document = Document::new(InstallOutput {
+                plan: plan.output(),
+                valid: result.is_ok(),
+            });
+            println!("{document}");
+        }
 
-    /* Validate whether the plan passes or fails */
-    match plan.validate(dots) {
-        Ok(plan) => {
+        result?;
+        if broken_names.is_empty() {
             info!("Looks Good! Nothing wrong with the current install plan!");
-            plan
+        } else {
+            warn!(
+                "Skipping {}, fix the Dot.toml to link it again",
+                broken_names.join(", ")
+            );
         }
-        Err(err) => {
-            error!("{}", err);
-            error!("Currently defined install would fail!");
-            process::exit(1)
+
+        if let Some(path) = save_plan {
+            plan.actions()
+                .and_then(|links| {
+                    let only = plan.only().map(<[String]>::to_vec);
+                    PlanFile { force, only, links }.save(path)
+                })
+                .map_err(|err| {
+                    DotsError::footprint(format!("Unable to save the install plan to {path}:"), err)
+                })?;
+            info!("Saved the install plan to {path}, use `dots apply {path}` to install it");
         }
+
+        /* Stopping here rolls back the cleanup as well, so a dry run leaves everything as it was */
+        if dry {
+            return Err(DotsError::Unsuccessful);
+        }
+
+        plan.execute(journal, fs_manager, force)
+            .map_err(|err| DotsError::footprint("Install Failed!", err))
+    })?;
+    if save_profile {
+        env.save_profile().unwrap_or_else(|err| {
+            warn!("Unable to remember the selected profile:\n{err}");
+        });
     }
+    info!("Install was a success!");
+    Ok(())
+}
 
-    if dry {
-        process::exit(1)
-    } else {
-        match plan.execute(&mut fs_manager, force) {
-            Ok(_) => {
-                info!("Install was a success!");
-                process::exit(0)
-            }
-            Err(err) => {
-                error!("Install Failed!");
-                error!("{}", err);
+#[derive(Serialize)]
+struct InstallOutput<'a> {
+    plan: PlanOutput<'a>,
+    valid: bool,
+}
 
-                process::exit(1)
+pub fn update(
+    env: &Environment,
+    name: &Option<String>,
+    force: Option<bool>,
+    format: Format,
+) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let force = force.or(env.config().update.force).unwrap_or(false);
+    let dots = match name {
+        Some(name) => match dots::find(name, env)? {
+            Some(dot) => vec![dot],
+            None => {
+                return Err(DotsError::NotFound(format!(
+                    "Unable to find an installed dot with the name: {name}"
+                )))
             }
-        }
+        },
+        None => dots::find_all(env)?,
+    };
+
+    /* Make sure every dot can be updated before updating any of them */
+    for dot in &dots {
+        dots::check_for_local_changes(dot)?;
+    }
+
+    for dot in &dots {
+        dots::update(dot, env)?;
+    }
+
+    install_all(env, None, force, false, false, &None, format)
+}
+
+/** Removes and recreates the links of a single installed dot, leaving every other dot alone */
+pub fn relink(
+    env: &Environment,
+    name: &str,
+    force: Option<bool>,
+    dry: bool,
+    diff: bool,
+    format: Format,
+) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let force = force.or(env.config().install.force).unwrap_or(false);
+    let Some(dot) = dots::find(name, env)? else {
+        return Err(DotsError::NotFound(format!(
+            "Unable to find an installed dot with the name: {name}"
+        )));
+    };
+    install_all(
+        env,
+        Some(vec![dot.package.name]),
+        force,
+        dry,
+        diff,
+        &None,
+        format,
+    )
+}
+
+/** Links everything in a plan saved with `install --save-plan`, as long as nothing has changed */
+pub fn apply(env: &Environment, path: &str) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let plan_file = PlanFile::read(path).map_err(|err| {
+        DotsError::parse(format!("Unable to read the install plan at {path}:"), err)
+    })?;
+
+    let changed: Vec<String> = plan_file
+        .links
+        .iter()
+        .filter(|planned| planned.has_changed())
+        .map(|planned| {
+            format!(
+                "{} has changed since the plan was made",
+                planned.link.dest.path
+            )
+        })
+        .collect();
+    if !changed.is_empty() {
+        return Err(DotsError::Invalid(format!(
+            "The install plan is out of date, save a new one with `dots install --dry --save-plan`\n{}",
+            changed.join("\n")
+        )));
     }
+
+    let links: Vec<Link> = plan_file.links.iter().map(|p| p.link.clone()).collect();
+    let outputs: Vec<Utf8PathBuf> = plan_file
+        .links
+        .iter()
+        .filter_map(|planned| planned.template.as_ref())
+        .map(|template| template.output.clone())
+        .collect();
+
+    let (_, broken) = dots::load_all(env)?;
+    let only = plan_file.only.as_deref();
+    let mut fs_manager = FSManager::init(env)?;
+    with_journal(&mut fs_manager, |journal, fs_manager| {
+        fs_manager
+            .clean(journal, &links, &broken, only, env)
+            .and_then(|_| fs_manager.clean_generated(journal, &outputs, &broken, only, env))
+            .map_err(|err| DotsError::footprint("failed to clean current install:", err))?;
+
+        plan::execute(&plan_file.links, journal, fs_manager, plan_file.force)
+            .map_err(|err| DotsError::footprint("Install Failed!", err))
+    })?;
+    info!("Install was a success!");
+    Ok(())
 }
 
-pub fn uninstall(name: &Option<String>) {
-    let env = Environment::new();
+pub fn lock(env: &Environment) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let dots = dots::find_all(env)?;
+    Lockfile::from_dots(&dots)
+        .and_then(|lockfile| lockfile.save(env))
+        .map_err(|err| DotsError::footprint("Unable to write lockfile:", err))?;
+    info!("Locked {} dots to their current commits", dots.len());
+    Ok(())
+}
+
+pub fn uninstall(env: &Environment, name: &Option<String>) -> Result<()> {
+    let _lock = lock_root(env)?;
     if let Some(name) = name {
-        dots::remove(name, &env).unwrap();
+        dots::remove(name, env)?;
     };
-    let plan = Plan::new(false);
-    let mut fs_manager = FSManager::init(&env);
-    let dots = dots::find_all(&env);
-    plan.clean(&env, &mut fs_manager, &dots)
-        .unwrap_or_else(|err| {
-            error!("failed to clean current install:");
-            error!("{}", err);
-            process::exit(1);
-        });
+    let (mut dots, broken) = dots::load_all(env)?;
+    let mut plan = Plan::new(false).with_broken(broken);
+    if let Some(name) = name {
+        plan = plan.with_only(vec![name.clone()]);
+    }
+    let mut fs_manager = FSManager::init(env)?;
+    with_journal(&mut fs_manager, |journal, fs_manager| {
+        plan.clean(env, journal, fs_manager, &mut dots)
+            .map_err(|err| DotsError::footprint("failed to clean current install:", err))
+    })
 }
 
-pub fn list(origins: bool) {
-    let env = Environment::new();
+pub fn list(env: &Environment, origins: bool, links: bool, format: Format) -> Result<()> {
+    let origins = origins || env.config().list.origins == Some(true);
+    let (dots, broken) = dots::load_all(env)?;
+    let fs_manager = FSManager::init(env)?;
+    let footprint = fs_manager.footprint();
+    if format == Format::Json {
+        let dots = dots.iter().map(|dot| ListedDotOutput {
+            name: &dot.package.name,
+            path: &dot.path,
+            origin: dot.origin(),
+            authors: &dot.package.authors,
+            links: footprint
+                .links_owned_by(&dot.package.name, env)
+                .map(|link| OwnedLinkOutput::new(link, footprint))
+                .collect(),
+        });
+        let document = Document::new(ListOutput {
+            root: env.root(),
+            dots: dots.collect(),
+            broken: broken.iter().map(BrokenDot::output).collect(),
+        });
+        println!("{document}");
+        return Ok(());
+    }
+
+    info!("Dots are installed in {}", env.root());
     let mut lines = vec![];
-    for dot in dots::find_all(&env) {
+    for dot in dots {
         let mut remote = String::new();
         if origins {
-            remote = format!(" => {}", dot.origin())
+            let origin = dot.origin().unwrap_or_else(|| dot.path.to_string());
+            remote = format!(" => {origin}")
         };
 
         let line = format!("{name}{remote}", name = dot.package.name);
         lines.push(line);
+        if links {
+            lines.extend(owned_link_lines(
+                &dot.package.name,
+                &dot.path,
+                footprint,
+                env,
+            ));
+        }
+    }
+    for dot in broken {
+        let line = format!("{} (broken, {})", dot.name, dot.error);
+        lines.push(format!("{}", styles::WARN.apply(line)));
+        if links {
+            lines.extend(owned_link_lines(&dot.name, &dot.path, footprint, env));
+        }
     }
 
     print!("{}", lines.join("\n"));
+    Ok(())
+}
+
+/**
+ * Describes every link the footprint says the given dot owns, one per line. Destinations are shown
+ * as they're written in the Dot.toml and sources relative to the dot when possible.
+ */
+fn owned_link_lines(
+    name: &str,
+    dot_path: &Utf8Path,
+    footprint: &Footprint,
+    env: &Environment,
+) -> Vec<String> {
+    footprint
+        .links_owned_by(name, env)
+        .map(|link| {
+            let dest = footprint
+                .records
+                .get(&link.dest.path)
+                .and_then(|record| record.key.clone())
+                .unwrap_or_else(|| link.dest.path.to_string());
+            let src = link
+                .src
+                .path
+                .strip_prefix(dot_path)
+                .unwrap_or(&link.src.path);
+            let line = match link.mode {
+                LinkMode::Symlink => format!("{dest} => {src}"),
+                mode => format!("{dest} => {src} ({mode})"),
+            };
+            indent(2, &line)
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct ListOutput<'a> {
+    root: Utf8PathBuf,
+    dots: Vec<ListedDotOutput<'a>>,
+    broken: Vec<BrokenDotOutput<'a>>,
+}
+
+#[derive(Serialize)]
+struct ListedDotOutput<'a> {
+    name: &'a str,
+    path: &'a Utf8Path,
+    origin: Option<String>,
+    authors: &'a [String],
+    links: Vec<OwnedLinkOutput<'a>>,
+}
+
+/// A link from the footprint, along with what was recorded when it was installed
+#[derive(Serialize)]
+struct OwnedLinkOutput<'a> {
+    src: &'a Utf8Path,
+    dest: &'a Utf8Path,
+    mode: LinkMode,
+    key: Option<&'a str>,
+    installed: Option<&'a str>,
+    dots_version: Option<&'a str>,
 }
 
-pub fn status() {
-    let env = Environment::new();
+impl<'a> OwnedLinkOutput<'a> {
+    fn new(link: &'a Link, footprint: &'a Footprint) -> Self {
+        let record = footprint.records.get(&link.dest.path);
+        OwnedLinkOutput {
+            src: &link.src.path,
+            dest: &link.dest.path,
+            mode: link.mode,
+            key: record.and_then(|record| record.key.as_deref()),
+            installed: record.and_then(|record| record.installed.as_deref()),
+            dots_version: record.and_then(|record| record.dots_version.as_deref()),
+        }
+    }
+}
+
+pub fn status(env: &Environment, format: Format) -> Result<()> {
+    let pins = Pins::read(env);
+    let fs_manager = FSManager::init(env)?;
     let mut lines: Vec<String> = vec![];
-    for dot in dots::find_all(&env) {
-        let status = git::get_status(&dot.path).unwrap_or_else(|error| {
-            error!("Unable to get dot status\n{}", error);
-            process::exit(1)
+    let mut documents = vec![];
+    for dot in dots::find_all(env)? {
+        let status = match dot.is_git() {
+            true => Some(
+                git::get_status(&dot.path)
+                    .map_err(|err| DotsError::git("Unable to get dot status", err))?,
+            ),
+            false => None,
+        };
+
+        let name = &dot.package.name;
+        if format == Format::Json {
+            let pin = pins.get(name);
+            let is_drifted = pin
+                .map(|pin| pin.is_drifted(&dot.path))
+                .transpose()
+                .map_err(|err| {
+                    DotsError::git(format!("Unable to compare {name} to its pinned ref"), err)
+                })?;
+            documents.push(DotStatusOutput {
+                name: name.clone(),
+                path: dot.path.clone(),
+                pin: pin.map(|pin| pin.output()),
+                drifted: is_drifted,
+                git: dot.is_git(),
+                files: status.as_deref().map(porcelain_entries),
+                copies: copy_status(&dot, fs_manager.footprint()),
+            });
+            continue;
+        }
+
+        match pins.get(name) {
+            Some(pin) => {
+                let header = format!("{name} (pinned to {pin})");
+                lines.push(format!("{}", styles::HEADER.apply(header)));
+
+                let is_drifted = pin.is_drifted(&dot.path).map_err(|err| {
+                    DotsError::git(format!("Unable to compare {name} to its pinned ref"), err)
+                })?;
+                if is_drifted {
+                    let warning = format!("checkout has drifted from {pin}");
+                    lines.push(indent(2, &styles::WARN.apply(warning).to_string()));
+                }
+            }
+            None => lines.push(format!("{}", styles::HEADER.apply(name))),
+        }
+        for message in copy_status(&dot, fs_manager.footprint()) {
+            lines.push(indent(2, &styles::WARN.apply(message).to_string()));
+        }
+        match status {
+            Some(status) => lines.push(indent(2, &status)),
+            None => lines.push(indent(2, &format!("not a git checkout ({})", dot.path))),
+        }
+    }
+
+    if format == Format::Json {
+        let document = Document::new(StatusOutput { dots: documents });
+        println!("{document}");
+    } else {
+        print!("{}", lines.join("\n"));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct StatusOutput<'a> {
+    dots: Vec<DotStatusOutput<'a>>,
+}
+
+#[derive(Serialize)]
+struct DotStatusOutput<'a> {
+    name: String,
+    path: Utf8PathBuf,
+    pin: Option<PinOutput<'a>>,
+    drifted: Option<bool>,
+    git: bool,
+    files: Option<Vec<FileStatusOutput>>,
+    copies: Vec<String>,
+}
+
+/// A file from `git status --porcelain=v1`, with its status in the index and the worktree
+#[derive(Serialize)]
+struct FileStatusOutput {
+    index: String,
+    worktree: String,
+    path: String,
+}
+
+/** Converts the lines of `git status --porcelain=v1` into an entry per file */
+fn porcelain_entries(status: &str) -> Vec<FileStatusOutput> {
+    status
+        .lines()
+        .filter(|line| line.len() > 3)
+        .map(|line| FileStatusOutput {
+            index: line[0..1].to_string(),
+            worktree: line[1..2].to_string(),
+            path: line[3..].to_string(),
+        })
+        .collect()
+}
+
+/** Describes every copied file in the given dot that no longer matches its source */
+fn copy_status(dot: &Dot, footprint: &Footprint) -> Vec<String> {
+    let mut messages = vec![];
+    for link in dot.links.iter().filter(|link| link.mode == LinkMode::Copy) {
+        let (Some(src), Some(dest)) = (&link.src.path, &link.dest.path) else {
+            continue;
+        };
+        let Some(recorded) = footprint.copies.get(dest) else {
+            continue;
+        };
+
+        let dest_name = &link.dest.original.path;
+        let src_hash = match &link.template {
+            Some(template) => Ok(hash(template.contents.as_bytes())),
+            None => hash_file(src),
+        };
+
+        if footprint.is_copy_modified(dest) == Some(true) {
+            messages.push(format!("{dest_name} has been changed since it was copied"));
+        } else if src_hash.is_ok_and(|hash| &hash != recorded) {
+            messages.push(format!(
+                "{dest_name} is out of date, run `dots install` to update it"
+            ));
+        }
+    }
+    messages
+}
+
+pub fn pull(env: &Environment, path: &str) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let mut fs_manager = FSManager::init(env)?;
+    let path = absolute(path);
+
+    let link = fs_manager
+        .footprint()
+        .links
+        .iter()
+        .find(|link| link.dest.path == path && link.mode == LinkMode::Copy)
+        .cloned();
+
+    let Some(link) = link else {
+        return Err(DotsError::NotFound(format!(
+            "{path} is not a file that was copied by dots"
+        )));
+    };
+
+    if link.src.path.starts_with(env.generated_path()) {
+        return Err(DotsError::Invalid(format!(
+            "{path} was rendered from a template. Make your changes to the template instead."
+        )));
+    }
+
+    let record = match fs_manager.footprint().records.get(&path) {
+        Some(record) => record.clone(),
+        None => match fs_manager.footprint().owner(&link, env) {
+            Some(owner) => LinkRecord::owned_by(&owner),
+            None => {
+                return Err(DotsError::NotFound(format!(
+                    "{path} was copied by dots, but no installed dot owns it"
+                )))
+            }
+        },
+    };
+    with_journal(&mut fs_manager, |journal, fs_manager| {
+        journal
+            .copy_file(&link.dest.path, &link.src.path)
+            .and_then(|_| fs_manager.track_link(&link, record))
+            .map_err(|err| {
+                DotsError::footprint(format!("Unable to copy {path} back into its dot:"), err)
+            })
+    })?;
+
+    info!("Copied {path} to {}", link.src.path);
+    Ok(())
+}
+
+/**
+ * Moves an existing file or directory into a dot, adds it to the dot's `[link]` table and links it
+ * back to where it was. If anything fails along the way, the file and Dot.toml are put back.
+ */
+pub fn adopt(
+    env: &Environment,
+    path: &str,
+    dot_name: &str,
+    as_path: &Option<String>,
+) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let mut fs_manager = FSManager::init(env)?;
+    let dest = absolute(path);
+
+    if dest.is_symlink() {
+        return Err(DotsError::Invalid(format!(
+            "{dest} is a symlink. Only regular files and directories can be adopted."
+        )));
+    } else if !dest.exists() {
+        return Err(DotsError::NotFound(format!("Unable to find {dest}")));
+    }
+
+    let Some(dot) = dots::find(dot_name, env)? else {
+        return Err(DotsError::NotFound(format!(
+            "Unable to find dot {dot_name}"
+        )));
+    };
+
+    let relative = match as_path {
+        Some(as_path) => Utf8PathBuf::from(as_path),
+        None => {
+            let Some(name) = dest.file_name() else {
+                return Err(DotsError::Invalid(format!("Unable to adopt {dest}")));
+            };
+            Utf8PathBuf::from(name.strip_prefix('.').unwrap_or(name))
+        }
+    };
+    if relative.is_absolute() || relative.as_str().split('/').any(|part| part == "..") {
+        return Err(DotsError::Invalid(format!(
+            "{relative} must be a relative path inside of the dot"
+        )));
+    }
+
+    let src = dot.path.join(&relative);
+    if src.symlink_metadata().is_ok() {
+        return Err(DotsError::Invalid(format!(
+            "{src} already exists, use --as to adopt {dest} under a different path"
+        )));
+    }
+    if dot
+        .links
+        .iter()
+        .any(|link| link.dest.path.as_ref() == Some(&dest))
+    {
+        return Err(DotsError::Invalid(format!(
+            "{dest} is already linked by {dot_name}"
+        )));
+    }
+
+    let key = match dest.strip_prefix(utils::fs::home()) {
+        Ok(relative) => format!("~/{relative}"),
+        Err(_) => dest.to_string(),
+    };
+    let package_path = dot.path.join("Dot.toml");
+    let previous = fs::read_to_string(&package_path)
+        .map_err(|err| DotsError::io(format!("Unable to read {package_path}:"), err))?;
+    let updated = insert_link(&previous, &key, relative.as_str())
+        .map_err(|err| DotsError::Invalid(err.to_string()))?;
+
+    let link = Link::new(&src, &dest);
+    let record = LinkRecord::new(&dot.package.name, &key);
+    with_journal(&mut fs_manager, |journal, fs_manager| {
+        (|| -> anyhow::Result<()> {
+            if let Some(parent) = src.parent() {
+                journal.create_dir_all(parent)?;
+            }
+            journal.move_path(&dest, &src)?;
+            journal.create_symlink(fs_manager, &link, record)?;
+            journal.write_file(&package_path, &updated)
+        })()
+        .map_err(|err| DotsError::footprint(format!("Unable to adopt {dest}:"), err))
+    })?;
+
+    info!("Moved {dest} to {src} and linked it back");
+    if dot.is_git() {
+        info!("Commit the changes in {} to keep them", dot.path);
+    }
+    Ok(())
+}
+
+/**
+ * Finds the dot that links the given path, along with where it lives in that dot. Paths inside of
+ * linked directories are looked up through the closest linked parent.
+ */
+pub fn which(env: &Environment, path: &str, format: Format) -> Result<()> {
+    let fs_manager = FSManager::init(env)?;
+    let path = absolute(path);
+
+    /* the closest destination wins when a linked directory contains other links */
+    let mut found: Option<(String, Utf8PathBuf, Link, Utf8PathBuf)> = None;
+    for dot in dots::find_all(env)? {
+        for resolved_link in &dot.links {
+            let Some(link) = resolved_link.as_link() else {
+                continue;
+            };
+            let is_closer = found
+                .as_ref()
+                .is_none_or(|(_, _, closest, _)| link.dest.path.starts_with(&closest.dest.path));
+            if path.starts_with(&link.dest.path) && is_closer {
+                let src = dot.path.join(&resolved_link.src.original.path);
+                found = Some((dot.package.name.clone(), dot.path.clone(), link, src));
+            }
+        }
+    }
+
+    let Some((name, dot_path, link, src)) = found else {
+        let tracked = fs_manager
+            .footprint()
+            .links
+            .iter()
+            .any(|link| path.starts_with(&link.dest.path));
+        return Err(DotsError::NotFound(match tracked {
+            true => format!("{path} was linked by dots, but no installed dot links it anymore. Run `dots install` to clean it up."),
+            false => format!("{path} isn't linked by any installed dot"),
+        }));
+    };
+
+    /* paths inside of a linked directory map to the same place inside of the dot */
+    let src = match path.strip_prefix(&link.dest.path) {
+        Ok(rest) if !rest.as_str().is_empty() => src.join(rest),
+        _ => src,
+    };
+    let relative = src.strip_prefix(&dot_path).unwrap_or(&src).to_path_buf();
+    let tracked = fs_manager.footprint().links.contains(&link);
+    let problem = if !link.exists() {
+        Some(format!(
+            "{} isn't linked to {}",
+            link.dest.path, link.src.path
+        ))
+    } else if fs_manager.footprint().is_copy_modified(&link.dest.path) == Some(true) {
+        Some(format!(
+            "{} has been changed since it was copied",
+            link.dest.path
+        ))
+    } else if src.symlink_metadata().is_err() {
+        Some(format!("{src} doesn't exist"))
+    } else {
+        None
+    };
+
+    if format == Format::Json {
+        let document = Document::new(WhichOutput {
+            path: &path,
+            dot: &name,
+            src: &src,
+            relative: &relative,
+            dest: &link.dest.path,
+            mode: link.mode,
+            tracked,
+            healthy: problem.is_none(),
+            problem: problem.as_deref(),
         });
+        println!("{document}");
+        return Ok(());
+    }
+
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+    let mut lines = vec![
+        format!("{}", styles::HEADER.apply(format!("{name} {relative}"))),
+        indent(2, &format!("source:  {src}")),
+        indent(2, &format!("link:    {} ({})", link.dest.path, link.mode)),
+        indent(2, &format!("tracked: {}", yes_no(tracked))),
+    ];
+    match problem {
+        None => lines.push(indent(2, "healthy: yes")),
+        Some(problem) => {
+            let problem = styles::WARN.apply(problem).to_string();
+            lines.push(indent(2, &format!("healthy: no, {problem}")));
+        }
+    }
+    print!("{}", lines.join("\n"));
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct WhichOutput<'a> {
+    path: &'a Utf8Path,
+    dot: &'a str,
+    src: &'a Utf8Path,
+    relative: &'a Utf8Path,
+    dest: &'a Utf8Path,
+    mode: LinkMode,
+    tracked: bool,
+    healthy: bool,
+    problem: Option<&'a str>,
+}
+
+/**
+ * Reports everything wrong with the installation and the footprint. With `fix`, problems that can
+ * be repaired without touching files dots didn't create are repaired. Exits with an error if any
+ * problems are left.
+ */
+pub fn doctor(env: &Environment, fix: bool, format: Format) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let mut fs_manager = FSManager::init_unchecked(env);
+    let problems = doctor::diagnose(env, &fs_manager)?;
+
+    let results: Vec<(doctor::Problem, bool)> = if fix {
+        with_journal(&mut fs_manager, |journal, fs_manager| {
+            let mut results = vec![];
+            for problem in problems {
+                let fixed = problem.fix.is_some()
+                    && match problem.repair(journal, fs_manager) {
+                        Ok(()) => true,
+                        Err(err) => {
+                            error!("Unable to fix {}:\n{err}", problem.message);
+                            false
+                        }
+                    };
+                results.push((problem, fixed));
+            }
+            Ok(results)
+        })?
+    } else {
+        problems
+            .into_iter()
+            .map(|problem| (problem, false))
+            .collect()
+    };
+    let remaining = results.iter().filter(|(_, fixed)| !fixed).count();
+
+    if format == Format::Json {
+        let problems = results
+            .iter()
+            .map(|(problem, fixed)| problem.output(*fixed))
+            .collect();
+        println!("{}", Document::new(DoctorOutput { problems }));
+    } else {
+        let lines: Vec<String> = results
+            .iter()
+            .map(|(problem, fixed)| {
+                let (first, rest) = problem
+                    .message
+                    .split_once('\n')
+                    .unwrap_or((&problem.message, ""));
+                let mut line = format!("- {first}");
+                if *fixed {
+                    line.push_str(" (fixed)");
+                }
+                if !rest.is_empty() {
+                    line = format!("{line}\n{}", indent(2, rest));
+                }
+                line
+            })
+            .collect();
+        print!("{}", lines.join("\n"));
+
+        let fixable = results
+            .iter()
+            .filter(|(problem, fixed)| problem.fix.is_some() && !fixed)
+            .count();
+        if results.is_empty() {
+            info!("No problems found!");
+        } else if fixable > 0 && !fix {
+            info!("Run `dots doctor --fix` to repair {fixable} of these problems");
+        }
+    }
 
-        lines.push(format!("{}", styles::HEADER.apply(dot.package.name)));
-        lines.push(indent(2, &status));
+    match remaining {
+        0 => Ok(()),
+        _ => Err(DotsError::Unsuccessful),
     }
+}
+
+#[derive(Serialize)]
+struct DoctorOutput<'a> {
+    problems: Vec<doctor::ProblemOutput<'a>>,
+}
+
+pub fn restore(env: &Environment, path: &Option<String>) -> Result<()> {
+    let _lock = lock_root(env)?;
+    let mut fs_manager = FSManager::init(env)?;
+
+    let Some(path) = path else {
+        let lines: Vec<String> = fs_manager
+            .footprint()
+            .backups
+            .iter()
+            .map(|backup| format!("{} ({})", backup.path, backup.created))
+            .collect();
+        print!("{}", lines.join("\n"));
+        return Ok(());
+    };
 
+    let path = absolute(path);
+    let Some(backup) = fs_manager.footprint().latest_backup(&path).cloned() else {
+        return Err(DotsError::NotFound(format!(
+            "Unable to find a backup for {path}"
+        )));
+    };
+
+    let mut link = None;
+    if path.is_symlink() {
+        link = fs_manager
+            .footprint()
+            .links
+            .iter()
+            .find(|link| link.dest.path == path)
+            .cloned();
+
+        if link.is_none() {
+            return Err(DotsError::Invalid(format!(
+                "{path} is a symlink that was not created by dots. Aborting."
+            )));
+        }
+    }
+
+    with_journal(&mut fs_manager, |journal, fs_manager| {
+        if let Some(link) = &link {
+            journal
+                .remove_symlink(&path)
+                .and_then(|_| fs_manager.untrack_link(link))
+                .map_err(|err| {
+                    DotsError::footprint(format!("Unable to remove symlink {path}:"), err)
+                })?;
+        }
+
+        journal
+            .restore_backup(fs_manager, &backup)
+            .and_then(|_| fs_manager.untrack_backup(&backup))
+            .map_err(|err| DotsError::footprint(format!("Unable to restore {path}:"), err))
+    })?;
+
+    info!("Restored {path} from backup taken at {}", backup.created);
+    Ok(())
+}
+
+fn read_config_file() -> Result<ConfigFile> {
+    ConfigFile::read(Config::path())
+}
+
+pub fn config_get(key: &str) -> Result<()> {
+    match read_config_file()?.get(key) {
+        Some(value) => {
+            print!("{}", display_value(value));
+            Ok(())
+        }
+        None => Err(DotsError::Unsuccessful),
+    }
+}
+
+pub fn config_set(key: &str, value: &str) -> Result<()> {
+    let mut config_file = read_config_file()?;
+    config_file
+        .set(key, value)
+        .map_err(|err| DotsError::Invalid(err.to_string()))?;
+    config_file.save()
+}
+
+pub fn config_list() -> Result<()> {
+    let lines: Vec<String> = read_config_file()?
+        .entries()
+        .iter()
+        .map(|(key, value)| format!("{key} = {}", display_value(value)))
+        .collect();
     print!("{}", lines.join("\n"));
+    Ok(())
 }
 
-pub fn path(name: &str) {
-    let env = Environment::new();
+pub fn path(env: &Environment, name: &Option<String>, format: Format) -> Result<()> {
+    let Some(name) = name else {
+        match format {
+            Format::Json => println!(
+                "{}",
+                Document::new(PathOutput {
+                    root: env.root(),
+                    name: None,
+                    path: None,
+                })
+            ),
+            Format::Text => print!("{}", env.root()),
+        }
+        return Ok(());
+    };
 
-    match dots::find_all(&env)
+    match dots::find_all(env)?
         .iter()
-        .find(|dot| dot.package.name == name)
+        .find(|dot| &dot.package.name == name)
     {
+        Some(dot) if format == Format::Json => {
+            let document = Document::new(PathOutput {
+                root: env.root(),
+                name: Some(name),
+                path: Some(&dot.path),
+            });
+            println!("{document}")
+        }
         Some(dot) => print!("{path}", path = dot.path),
-        None => process::exit(1),
+        None => return Err(DotsError::Unsuccessful),
     }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct PathOutput<'a> {
+    root: Utf8PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<&'a Utf8Path>,
 }
@@ -0,0 +1,298 @@
+use std::{env, fmt};
+
+/*=========*\
+*  Machine  *
+\*=========*/
+
+/// The properties of the current machine that links can be conditional on
+#[derive(Clone, Debug)]
+pub struct Machine {
+    pub os: String,
+    pub arch: String,
+    pub family: String,
+    pub hostname: String,
+}
+
+impl Machine {
+    /**
+     * Detects the properties of the current machine. The hostname can be overridden with the
+     * `DOTS_HOSTNAME` environment variable.
+     */
+    pub fn current() -> Machine {
+        let hostname = env::var("DOTS_HOSTNAME")
+            .unwrap_or_else(|_| gethostname::gethostname().to_string_lossy().to_string());
+
+        Machine {
+            os: env::consts::OS.to_string(),
+            arch: env::consts::ARCH.to_string(),
+            family: env::consts::FAMILY.to_string(),
+            hostname,
+        }
+    }
+
+    fn get(&self, key: &Key) -> &str {
+        match key {
+            Key::Os => &self.os,
+            Key::Arch => &self.arch,
+            Key::Family => &self.family,
+            Key::Hostname => &self.hostname,
+        }
+    }
+}
+
+/*============*\
+*  Conditions  *
+\*============*/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Os,
+    Arch,
+    Family,
+    Hostname,
+}
+
+impl Key {
+    fn parse(name: &str) -> Result<Key, String> {
+        match name {
+            "os" => Ok(Key::Os),
+            "arch" => Ok(Key::Arch),
+            "family" => Ok(Key::Family),
+            "hostname" => Ok(Key::Hostname),
+            _ => Err(format!(
+                "Unknown key \"{name}\". Expected one of os, arch, family or hostname"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Os => write!(f, "os"),
+            Key::Arch => write!(f, "arch"),
+            Key::Family => write!(f, "family"),
+            Key::Hostname => write!(f, "hostname"),
+        }
+    }
+}
+
+/// A `cfg(...)` style expression that can be checked against a [`Machine`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Is(Key, String),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /** Parses a condition in the form of `cfg(os = "linux")` */
+    pub fn parse(input: &str) -> Result<Condition, String> {
+        let mut parser = Parser::new(input);
+        parser.expect_word("cfg")?;
+        parser.expect('(')?;
+        let condition = parser.condition()?;
+        parser.expect(')')?;
+        parser.expect_end()?;
+        Ok(condition)
+    }
+
+    pub fn matches(&self, machine: &Machine) -> bool {
+        match self {
+            Condition::Is(key, value) => machine.get(key) == value,
+            Condition::All(conditions) => conditions.iter().all(|c| c.matches(machine)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.matches(machine)),
+            Condition::Not(condition) => !condition.matches(machine),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |conditions: &Vec<Condition>| {
+            conditions
+                .iter()
+                .map(|condition| condition.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        match self {
+            Condition::Is(key, value) => write!(f, "{key} = \"{value}\""),
+            Condition::All(conditions) => write!(f, "all({})", join(conditions)),
+            Condition::Any(conditions) => write!(f, "any({})", join(conditions)),
+            Condition::Not(condition) => write!(f, "not({condition})"),
+        }
+    }
+}
+
+/*========*\
+*  Parser  *
+\*========*/
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser { input, position: 0 }
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let word = self.word()?;
+        match word.as_str() {
+            "all" => Ok(Condition::All(self.list()?)),
+            "any" => Ok(Condition::Any(self.list()?)),
+            "not" => {
+                self.expect('(')?;
+                let condition = self.condition()?;
+                self.expect(')')?;
+                Ok(Condition::Not(Box::new(condition)))
+            }
+            _ => {
+                let key = Key::parse(&word)?;
+                self.expect('=')?;
+                let value = self.string()?;
+                Ok(Condition::Is(key, value))
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<Vec<Condition>, String> {
+        self.expect('(')?;
+        let mut conditions = vec![self.condition()?];
+        while self.peek() == Some(',') {
+            self.expect(',')?;
+            conditions.push(self.condition()?);
+        }
+        self.expect(')')?;
+        Ok(conditions)
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        let rest = &self.input[self.position..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("a name"));
+        }
+        self.position += len;
+        Ok(rest[..len].to_string())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let rest = &self.input[self.position..];
+        let Some(len) = rest.find('"') else {
+            return Err(String::from("Unterminated string"));
+        };
+        self.position += len + 1;
+        Ok(rest[..len].to_string())
+    }
+
+    fn expect_word(&mut self, expected: &str) -> Result<(), String> {
+        let start = self.position;
+        match self.word() {
+            Ok(word) if word == expected => Ok(()),
+            _ => {
+                self.position = start;
+                Err(self.error(&format!("\"{expected}\"")))
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("\"{expected}\"")))
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("the end of the condition")),
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.position..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, expected: &str) -> String {
+        format!("Expected {expected} at position {}", self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod condition {
+        use crate::condition::{Condition, Key, Machine};
+
+        fn machine() -> Machine {
+            Machine {
+                os: String::from("linux"),
+                arch: String::from("x86_64"),
+                family: String::from("unix"),
+                hostname: String::from("workstation"),
+            }
+        }
+
+        #[test]
+        fn it_should_parse_a_single_key() {
+            let condition = Condition::parse(r#"cfg(os = "linux")"#).unwrap();
+            assert_eq!(condition, Condition::Is(Key::Os, String::from("linux")));
+        }
+
+        #[test]
+        fn it_should_parse_nested_conditions() {
+            let condition =
+                Condition::parse(r#"cfg(all(family = "unix", not(any(arch="arm", os="macos"))))"#)
+                    .unwrap();
+            assert_eq!(
+                condition.to_string(),
+                r#"all(family = "unix", not(any(arch = "arm", os = "macos")))"#
+            );
+        }
+
+        #[test]
+        fn it_should_match_against_the_machine() {
+            let machine = machine();
+            let matches = |input: &str| Condition::parse(input).unwrap().matches(&machine);
+            assert!(matches(r#"cfg(os = "linux")"#));
+            assert!(!matches(r#"cfg(os = "macos")"#));
+            assert!(matches(
+                r#"cfg(all(os = "linux", hostname = "workstation"))"#
+            ));
+            assert!(matches(r#"cfg(any(os = "macos", arch = "x86_64"))"#));
+            assert!(!matches(r#"cfg(not(family = "unix"))"#));
+        }
+
+        #[test]
+        fn it_should_fail_on_unknown_keys() {
+            let error = Condition::parse(r#"cfg(distro = "arch")"#).unwrap_err();
+            assert!(error.starts_with("Unknown key \"distro\""));
+        }
+
+        #[test]
+        fn it_should_fail_on_malformed_conditions() {
+            assert!(Condition::parse(r#"os = "linux""#).is_err());
+            assert!(Condition::parse(r#"cfg(os = "linux""#).is_err());
+            assert!(Condition::parse(r#"cfg(os = linux)"#).is_err());
+            assert!(Condition::parse(r#"cfg(os = "linux") extra"#).is_err());
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use anyhow::{anyhow, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::BTreeMap;
+use std::{env, fs, io};
+use toml::value::{Table, Value};
+use utils::fs::home;
+
+use crate::error::DotsError;
+use crate::output::ColorChoice;
+
+/*========*\
+*  Config  *
+\*========*/
+
+/// Defaults for the cli itself, read from `~/.config/dots/config.toml`. Flags always win.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The directory dots are installed to when `DOTS_HOME` and `--root` aren't given
+    pub root: Option<String>,
+    pub color: Option<ColorChoice>,
+    #[serde(default)]
+    pub install: InstallDefaults,
+    #[serde(default)]
+    pub update: UpdateDefaults,
+    #[serde(default)]
+    pub list: ListDefaults,
+    /// Overrides for the properties of this machine that conditional links are checked against
+    #[serde(default)]
+    pub machine: MachineDefaults,
+    /// Template variables for this machine, shared by every dot
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Short names for dot urls, so `dots add <name>` can be used in place of a url
+    #[serde(default)]
+    pub registry: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct InstallDefaults {
+    pub force: Option<bool>,
+    /// The profile to install when one hasn't been chosen with `--profile`
+    pub profile: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateDefaults {
+    pub force: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ListDefaults {
+    pub origins: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct MachineDefaults {
+    pub hostname: Option<String>,
+}
+
+impl Config {
+    /**
+     * Where the config file lives. Uses `DOTS_CONFIG` if it's set, otherwise the `dots` directory
+     * in `XDG_CONFIG_HOME` or `~/.config`.
+     */
+    pub fn path() -> Utf8PathBuf {
+        if let Ok(path) = env::var("DOTS_CONFIG") {
+            if !path.is_empty() {
+                return utils::fs::absolute(path);
+            }
+        }
+        let config_home = match env::var("XDG_CONFIG_HOME") {
+            Ok(path) if !path.is_empty() => utils::fs::absolute(path),
+            _ => home().join(".config"),
+        };
+        config_home.join("dots").join("config.toml")
+    }
+
+    /**
+     * Reads the config file, falling back to the defaults if it's missing. A config that can't be
+     * read or parsed is an error, since ignoring it could send every command to a different root.
+     */
+    pub fn read<P>(path: P) -> Result<Config, DotsError>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let path = path.as_ref();
+        let string = match fs::read_to_string(path) {
+            Ok(string) => string,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+                ) =>
+            {
+                return Ok(Config::default())
+            }
+            Err(err) => return Err(DotsError::io(format!("Unable to read {path}:"), err)),
+        };
+        toml::from_str(&string)
+            .map_err(|err| DotsError::Invalid(format!("Unable to parse {path}:\n{err}")))
+    }
+}
+
+/*==============*\
+*  Config Files  *
+\*==============*/
+
+/// The raw contents of the config file, used by `dots config` to get and set single keys
+pub struct ConfigFile {
+    path: Utf8PathBuf,
+    table: Table,
+}
+
+impl ConfigFile {
+    /** Reads the config file, starting from an empty table if it's missing */
+    pub fn read<P>(path: P) -> Result<ConfigFile, DotsError>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let table = match fs::read_to_string(&path) {
+            Ok(string) => toml::from_str(&string)
+                .map_err(|err| DotsError::parse(format!("Unable to parse {path}:"), err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Table::new(),
+            Err(err) => return Err(DotsError::io(format!("Unable to read {path}:"), err)),
+        };
+        Ok(ConfigFile { path, table })
+    }
+
+    /** Looks up a dotted key such as `install.force` */
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let mut parts = key.split('.');
+        let mut value = self.table.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(value)
+    }
+
+    /**
+     * Sets a dotted key to the given value. Values that look like booleans or integers are stored
+     * as such unless the key only takes strings, everything else is stored as a string. Fails if
+     * the result isn't a valid config.
+     */
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let typed = match value {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            _ => match value.parse::<i64>() {
+                Ok(number) => Value::Integer(number),
+                Err(_) => Value::String(value.to_string()),
+            },
+        };
+
+        let table = match self.with_value(key, typed) {
+            Ok(table) => table,
+            /* keys such as variables and machine.hostname hold strings that can look like numbers */
+            Err(err) => self
+                .with_value(key, Value::String(value.to_string()))
+                .map_err(|_| err)?,
+        };
+        self.table = table;
+        Ok(())
+    }
+
+    /** A copy of the table with the given key set, as long as the result is a valid config */
+    fn with_value(&self, key: &str, value: Value) -> Result<Table> {
+        let mut table = self.table.clone();
+        let (parents, name) = match key.rsplit_once('.') {
+            Some((parents, name)) => (parents.split('.').collect(), name),
+            None => (vec![], key),
+        };
+        let mut current = &mut table;
+        for part in parents {
+            current = current
+                .entry(part)
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| anyhow!("{part} is not a table"))?;
+        }
+        current.insert(name.to_string(), value);
+
+        Value::Table(table.clone())
+            .try_into::<Config>()
+            .map_err(|err| anyhow!("Invalid value for {key}: {err}"))?;
+        Ok(table)
+    }
+
+    /** Every key in the file along with its value, sorted by key */
+    pub fn entries(&self) -> Vec<(String, Value)> {
+        fn flatten(prefix: &str, table: &Table, entries: &mut Vec<(String, Value)>) {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                match value {
+                    Value::Table(table) => flatten(&key, table, entries),
+                    value => entries.push((key, value.clone())),
+                }
+            }
+        }
+
+        let mut entries = vec![];
+        flatten("", &self.table, &mut entries);
+        entries
+    }
+
+    pub fn save(&self) -> Result<(), DotsError> {
+        let path = &self.path;
+        /* Serializing through a Value makes sure plain keys are written before any tables */
+        let contents = toml::to_string(&Value::Table(self.table.clone()))
+            .map_err(|err| DotsError::parse(format!("Unable to write {path}:"), err))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| DotsError::io(format!("Unable to create {parent}:"), err))?;
+        }
+        fs::write(path, contents)
+            .map_err(|err| DotsError::io(format!("Unable to write {path}:"), err))
+    }
+}
+
+/** Formats a config value the way it would be passed to `dots config set` */
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod config_file {
+        use crate::config::{display_value, Config, ConfigFile};
+        use crate::error::DotsError;
+        use camino::Utf8Path;
+        use tempfile::tempdir;
+
+        #[test]
+        fn it_should_set_and_get_nested_keys() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("config.toml");
+            let mut file = ConfigFile::read(&path).unwrap();
+
+            file.set("install.force", "true").unwrap();
+            file.set("variables.email", "me@example.com").unwrap();
+            file.set("root", "~/dotfiles").unwrap();
+            file.save().unwrap();
+
+            let file = ConfigFile::read(&path).unwrap();
+            assert_eq!(display_value(file.get("install.force").unwrap()), "true");
+            assert_eq!(
+                display_value(file.get("variables.email").unwrap()),
+                "me@example.com"
+            );
+
+            let config = Config::read(&path).unwrap();
+            assert_eq!(config.install.force, Some(true));
+            assert_eq!(config.root, Some(String::from("~/dotfiles")));
+        }
+
+        #[test]
+        fn it_should_store_values_that_look_like_numbers_as_strings_for_string_keys() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("config.toml");
+            let mut file = ConfigFile::read(&path).unwrap();
+
+            file.set("variables.port", "8080").unwrap();
+            file.set("variables.debug", "true").unwrap();
+            file.set("machine.hostname", "1234").unwrap();
+            file.save().unwrap();
+
+            let config = Config::read(&path).unwrap();
+            assert_eq!(config.variables["port"], "8080");
+            assert_eq!(config.variables["debug"], "true");
+            assert_eq!(config.machine.hostname, Some(String::from("1234")));
+            assert!(file.set("install.force", "1").is_err());
+        }
+
+        #[test]
+        fn it_should_refuse_to_read_a_config_that_cant_be_parsed() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("config.toml");
+
+            assert_eq!(Config::read(&path).unwrap(), Config::default());
+            std::fs::write(&path, "roto = \"~/dotfiles\"\n").unwrap();
+            assert!(matches!(Config::read(&path), Err(DotsError::Invalid(_))));
+        }
+
+        #[test]
+        fn it_should_refuse_to_read_a_config_file_that_isnt_utf8() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("config.toml");
+
+            std::fs::write(&path, [0xff, 0xfe]).unwrap();
+            assert!(matches!(ConfigFile::read(&path), Err(DotsError::Io { .. })));
+        }
+
+        #[test]
+        fn it_should_list_every_key() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("config.toml");
+            let mut file = ConfigFile::read(&path).unwrap();
+
+            file.set("registry.work", "git@example.com:me/work.git")
+                .unwrap();
+            file.set("color", "never").unwrap();
+
+            let keys: Vec<String> = file.entries().into_iter().map(|(key, _)| key).collect();
+            assert_eq!(keys, vec!["color", "registry.work"]);
+        }
+
+        #[test]
+        fn it_should_reject_unknown_keys_and_invalid_values() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("config.toml");
+            let mut file = ConfigFile::read(&path).unwrap();
+
+            assert!(file.set("install.forse", "true").is_err());
+            assert!(file.set("install.force", "yes").is_err());
+            assert!(file.set("color", "sometimes").is_err());
+            assert!(file.get("install").is_none());
+        }
+    }
+}
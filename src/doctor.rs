@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::BTreeSet;
+use std::time::SystemTime;
+use utils::fs::home;
+use utils::git;
+
+use crate::dot_package::DotPackageConfig;
+use crate::dots::{self, Environment};
+use crate::error::DotsError;
+use crate::footprint::{Footprint, LinkRecord};
+use crate::fs_manager::FSManager;
+use crate::plan::journal::Journal;
+use crate::plan::links::{Link, LinkMode};
+
+/*==========*\
+*  Problems  *
+\*==========*/
+
+/// Something wrong with the installation that `dots doctor` found
+#[derive(Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    pub message: String,
+    /// How the problem can be repaired, if it can be repaired safely
+    pub fix: Option<Fix>,
+}
+
+/// How a problem is described in JSON output
+#[derive(Serialize)]
+pub struct ProblemOutput<'a> {
+    kind: ProblemKind,
+    message: &'a str,
+    fixable: bool,
+    fixed: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// git couldn't be found on the PATH
+    MissingGit,
+    /// The footprint file exists but can't be parsed
+    UnreadableFootprint,
+    /// A dot's Dot.toml can't be read or parsed
+    BrokenDot,
+    /// A dot's `package.name` doesn't match the name it was installed under
+    MismatchedName,
+    /// A tracked symlink points to a dotfile that doesn't exist
+    DanglingLink,
+    /// A tracked link is no longer at its destination
+    MissingLink,
+    /// A symlink points into a dot but isn't tracked in the footprint
+    UntrackedLink,
+    /// A tracked link belongs to a dot that has been removed
+    RemovedDot,
+}
+
+/// A repair that only touches files that dots created
+#[derive(Debug, PartialEq, Eq)]
+pub enum Fix {
+    /// Moves the unreadable footprint to the given timestamped path so a new one can be started
+    ResetFootprint(Utf8PathBuf, Utf8PathBuf),
+    /// Removes the link from the footprint, leaving the fs alone
+    Untrack(Link),
+    /// Removes the link from the footprint, along with its symlink if it still points to the dotfile
+    Remove(Link),
+    /// Adds a link that is already on the fs to the footprint, along with where it came from
+    Track(Link, LinkRecord),
+    /// Removes an untracked symlink that points to nothing
+    RemoveSymlink(Utf8PathBuf),
+}
+
+impl Problem {
+    fn new(kind: ProblemKind, message: String, fix: Option<Fix>) -> Problem {
+        Problem { kind, message, fix }
+    }
+
+    /** Applies the fix for this problem, recording every change to the fs in the given journal */
+    pub fn repair(&self, journal: &mut Journal, fs_manager: &mut FSManager) -> Result<()> {
+        match &self.fix {
+            None => {}
+            Some(Fix::ResetFootprint(path, backup)) => {
+                if backup.symlink_metadata().is_ok() {
+                    return Err(anyhow!("{backup} already exists"));
+                }
+                journal.move_path(path, backup)?
+            }
+            Some(Fix::Untrack(link)) => fs_manager.untrack_link(link)?,
+            Some(Fix::Remove(link)) => {
+                if link.mode == LinkMode::Symlink && link.exists() {
+                    journal.remove_symlink(&link.dest.path)?;
+                }
+                fs_manager.untrack_link(link)?;
+            }
+            Some(Fix::Track(link, record)) => fs_manager.track_link(link, record.clone())?,
+            Some(Fix::RemoveSymlink(path)) => journal.remove_symlink(path)?,
+        }
+        Ok(())
+    }
+
+    pub fn output(&self, fixed: bool) -> ProblemOutput<'_> {
+        ProblemOutput {
+            kind: self.kind,
+            message: &self.message,
+            fixable: self.fix.is_some(),
+            fixed,
+        }
+    }
+}
+
+/** Where an unreadable footprint is moved to, timestamped so earlier backups are kept */
+fn backup_path(path: &Utf8Path) -> Utf8PathBuf {
+    let timestamp = humantime::format_rfc3339_seconds(SystemTime::now());
+    Utf8PathBuf::from(format!("{path}.{timestamp}.bak"))
+}
+
+/*===========*\
+*  Diagnosis  *
+\*===========*/
+
+/**
+ * Cross-checks the footprint against the fs and every Dot.toml. Problems that can be repaired are
+ * listed in the order their fixes should be applied.
+ */
+pub fn diagnose(env: &Environment, fs_manager: &FSManager) -> Result<Vec<Problem>, DotsError> {
+    let mut problems = vec![];
+
+    if !git::is_installed() {
+        problems.push(Problem::new(
+            ProblemKind::MissingGit,
+            String::from("git is not installed or isn't on your PATH"),
+            None,
+        ));
+    }
+
+    let footprint_path = env.footprint_path();
+    if let Some(contents) = fs_manager.footprint_contents() {
+        if let Err(err) = Footprint::parse(&contents) {
+            let backup = backup_path(&footprint_path);
+            problems.push(Problem::new(
+                ProblemKind::UnreadableFootprint,
+                format!("Unable to parse {footprint_path}, it will be moved to {backup}:\n{err}"),
+                Some(Fix::ResetFootprint(footprint_path.clone(), backup)),
+            ));
+        }
+    }
+
+    let (dots, broken) = dots::load_all(env)?;
+    for dot in &broken {
+        problems.push(Problem::new(
+            ProblemKind::BrokenDot,
+            format!("{}: {}", dot.path, dot.error),
+            None,
+        ));
+    }
+    for dir in dots::dot_dirs(env)? {
+        let Ok(config) = DotPackageConfig::read_and_parse(&dir.path) else {
+            continue;
+        };
+        if config.package.name != dir.name {
+            problems.push(Problem::new(
+                ProblemKind::MismatchedName,
+                format!(
+                    "{} contains the dot {}, but it's installed as {}",
+                    dir.path, config.package.name, dir.name
+                ),
+                None,
+            ))
+        }
+    }
+    let valid_links: Vec<Link> = dots
+        .iter()
+        .flat_map(|dot| dot.links.iter().filter_map(|link| link.as_link()))
+        .collect();
+    let generated_path = env.generated_path();
+    let belongs_to = |src: &Utf8Path, name: &str, path: &Utf8Path| {
+        src.starts_with(path) || src.starts_with(generated_path.join(name))
+    };
+
+    for link in &fs_manager.footprint().links {
+        let src = &link.src.path;
+        let dest = &link.dest.path;
+        let is_loaded = dots
+            .iter()
+            .any(|dot| belongs_to(src, &dot.package.name, &dot.path));
+        let is_broken = broken.iter().any(|dot| dot.owns(src, env));
+
+        if !is_loaded && is_broken {
+            /* the dot is still there but can't be loaded, which is reported above */
+            continue;
+        } else if !is_loaded && env.is_dot_path(src) {
+            problems.push(Problem::new(
+                ProblemKind::RemovedDot,
+                format!("{dest} is tracked, but the dot that linked it has been removed"),
+                Some(Fix::Remove(link.clone())),
+            ));
+        } else if link.mode == LinkMode::Symlink && dest.is_symlink() && !src.exists() {
+            problems.push(Problem::new(
+                ProblemKind::DanglingLink,
+                format!("{dest} points to {src}, which doesn't exist"),
+                Some(Fix::Remove(link.clone())),
+            ));
+        } else if !link.exists() {
+            problems.push(Problem::new(
+                ProblemKind::MissingLink,
+                format!("{dest} is tracked, but it isn't linked to {src}"),
+                Some(Fix::Untrack(link.clone())),
+            ));
+        }
+    }
+
+    /* dots can link anywhere, so only the usual places are searched for untracked links */
+    let mut candidates: BTreeSet<Utf8PathBuf> = valid_links
+        .iter()
+        .map(|link| link.dest.path.clone())
+        .collect();
+    for dir in [home(), home().join(".config")] {
+        let Ok(entries) = dir.read_dir_utf8() else {
+            continue;
+        };
+        candidates.extend(entries.flatten().map(|entry| entry.path().to_path_buf()));
+    }
+
+    let tracked: BTreeSet<&Utf8PathBuf> = fs_manager
+        .footprint()
+        .links
+        .iter()
+        .map(|link| &link.dest.path)
+        .collect();
+    for path in candidates {
+        if !path.is_symlink() || tracked.contains(&path) {
+            continue;
+        }
+        let Ok(target) = path.read_link_utf8() else {
+            continue;
+        };
+        let target = match path.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        };
+        if !env.is_dot_path(&target) {
+            continue;
+        }
+
+        let link = Link::new(&target, &path);
+        let record = dots.iter().find_map(|dot| {
+            dot.links
+                .iter()
+                .find(|resolved| resolved.as_link().as_ref() == Some(&link))
+                .map(|resolved| {
+                    let key = resolved.dest.original.path.as_str();
+                    LinkRecord::new(&dot.package.name, key)
+                })
+        });
+        let fix = if let Some(record) = record {
+            Some(Fix::Track(link, record))
+        } else if !target.exists() {
+            Some(Fix::RemoveSymlink(path.clone()))
+        } else {
+            None
+        };
+        problems.push(Problem::new(
+            ProblemKind::UntrackedLink,
+            format!("{path} links to {target}, but isn't tracked"),
+            fix,
+        ));
+    }
+
+    Ok(problems)
+}
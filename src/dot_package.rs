@@ -1,9 +1,11 @@
 use anyhow::{anyhow, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use std::collections::BTreeMap;
-use std::fs;
 use std::io::{self, Read};
 use std::path::Path;
+use std::{fmt, fs};
+
+use crate::plan::links::LinkMode;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
@@ -16,33 +18,197 @@ pub struct DotPackageMeta {
 #[serde(deny_unknown_fields)]
 pub struct DotPackageConfig {
     pub package: DotPackageMeta,
-    pub link: BTreeMap<Utf8PathBuf, Utf8PathBuf>,
+    #[serde(default)]
+    pub link: BTreeMap<Utf8PathBuf, DotLink>,
+    /// Links that only apply when a `cfg(...)` condition matches the current machine
+    #[serde(default)]
+    pub target: BTreeMap<String, DotLinkTable>,
+    /// Links that only apply on the machine with the given hostname
+    #[serde(default)]
+    pub host: BTreeMap<String, DotLinkTable>,
+    /// Files that are rendered with the dot's variables before being linked
+    #[serde(default)]
+    pub template: BTreeMap<Utf8PathBuf, DotLink>,
+    /// Default values for the variables used in templates
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Named subsets of links that can be selected at install time
+    #[serde(default)]
+    pub profile: BTreeMap<String, DotProfile>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct DotLinkTable {
+    #[serde(default)]
+    pub link: BTreeMap<Utf8PathBuf, DotLink>,
+}
+
+/// The source of a link. Either just a path (`"~/.bashrc" = "shell/bashrc"`) or a table with
+/// extra options (`"~/.bashrc" = { src = "shell/bashrc", mode = "copy" }`).
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum DotLink {
+    Path(Utf8PathBuf),
+    Table(DotLinkOptions),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct DotLinkOptions {
+    pub src: Utf8PathBuf,
+    #[serde(default)]
+    pub mode: LinkMode,
+}
+
+impl DotLink {
+    pub fn src(&self) -> &Utf8Path {
+        match self {
+            DotLink::Path(src) => src,
+            DotLink::Table(options) => &options.src,
+        }
+    }
+
+    pub fn mode(&self) -> LinkMode {
+        match self {
+            DotLink::Path(_) => LinkMode::default(),
+            DotLink::Table(options) => options.mode,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct DotProfile {
+    /// The destinations of every link that this profile enables
+    pub links: Vec<Utf8PathBuf>,
 }
 
 impl DotPackageConfig {
-    pub fn read_and_parse<P>(path: P) -> Result<DotPackageConfig>
+    /** Reads and parses the Dot.toml in the given directory */
+    pub fn read_and_parse<P>(path: P) -> std::result::Result<DotPackageConfig, DotError>
     where
         P: AsRef<Utf8Path>,
     {
-        let path = path.as_ref();
-        let contents = match read_package(path.join("Dot.toml")) {
-            Ok(contents) => contents,
-            Err(err) => {
-                error!("Error reading Dot.toml:\n{}", err);
-                return Err(anyhow!("Error reading Dot.toml"));
+        let contents = read_package(path.as_ref().join("Dot.toml")).map_err(DotError::Read)?;
+        parse_package(contents).map_err(DotError::from)
+    }
+}
+
+/*========*\
+*  Errors  *
+\*========*/
+
+/// Why a dot couldn't be loaded
+#[derive(Debug)]
+pub enum DotError {
+    /// The Dot.toml couldn't be read
+    Read(io::Error),
+    /// The Dot.toml isn't valid toml or doesn't match the Dot.toml format
+    Parse {
+        message: String,
+        /// The line and column of the error, both starting at 1
+        span: Option<(usize, usize)>,
+    },
+    /// The Dot.toml parsed but describes something invalid, such as an unknown target
+    Invalid(String),
+}
+
+impl From<toml::de::Error> for DotError {
+    fn from(err: toml::de::Error) -> DotError {
+        let span = err.line_col().map(|(line, column)| (line + 1, column + 1));
+        let mut message = err.to_string();
+        if let Some((line, column)) = span {
+            let location = format!(" at line {line} column {column}");
+            if let Some(stripped) = message.strip_suffix(&location) {
+                message = stripped.to_string();
             }
-        };
+        }
+        DotError::Parse { message, span }
+    }
+}
+
+impl fmt::Display for DotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DotError::Read(err) => write!(f, "Unable to read Dot.toml: {err}"),
+            DotError::Parse {
+                message,
+                span: Some((line, column)),
+            } => write!(f, "Dot.toml:{line}:{column}: {message}"),
+            DotError::Parse {
+                message,
+                span: None,
+            } => write!(f, "Dot.toml: {message}"),
+            DotError::Invalid(message) => write!(f, "Dot.toml: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for DotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DotError::Read(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/**
+ * Adds a `"dest" = "src"` entry to the `[link]` table of a Dot.toml's raw contents. Everything else,
+ * including comments and formatting, is left as it was. A `[link]` table is appended if the file
+ * doesn't have one yet. Fails if the result is no longer a valid Dot.toml.
+ */
+pub fn insert_link(contents: &str, dest: &str, src: &str) -> Result<String> {
+    let existing =
+        parse_package(contents).map_err(|err| anyhow!("Unable to parse Dot.toml:\n{err}"))?;
+    if existing.link.contains_key(Utf8Path::new(dest)) {
+        return Err(anyhow!("{dest} is already linked by this dot"));
+    }
 
-        let package = match parse_package(contents) {
-            Ok(package) => package,
-            Err(err) => {
-                error!("Error parsing Dot.toml:\n{}", err);
-                return Err(anyhow!("Error reading Dot.toml"));
+    let quote = |value: &str| toml::Value::String(value.to_string()).to_string();
+    let entry = format!("{} = {}", quote(dest), quote(src));
+
+    let lines: Vec<&str> = contents.lines().collect();
+    let is_header = |line: &str| line.trim_start().starts_with('[');
+    let is_link_header = |line: &str| {
+        let line = line.trim();
+        line == "[link]" || (line.starts_with("[link]") && line[6..].trim_start().starts_with('#'))
+    };
+
+    let mut result: Vec<&str> = vec![];
+    match lines.iter().position(|line| is_link_header(line)) {
+        Some(start) => {
+            /* insert after the last entry of the table, before any blank lines or the next table */
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| is_header(line))
+                .map_or(lines.len(), |i| start + 1 + i);
+            let last = lines[start..end]
+                .iter()
+                .rposition(|line| !line.trim().is_empty())
+                .map_or(start, |i| start + i);
+            result.extend(&lines[..=last]);
+            result.push(&entry);
+            result.extend(&lines[last + 1..]);
+        }
+        None => {
+            result.extend(&lines);
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                result.push("");
             }
-        };
+            result.push("[link]");
+            result.push(&entry);
+        }
+    }
 
-        Ok(package)
+    let updated = result.join("\n") + "\n";
+    let package = parse_package(&updated)
+        .map_err(|err| anyhow!("Unable to add {dest} to the [link] table:\n{err}"))?;
+    if package.link.get(Utf8Path::new(dest)).map(DotLink::src) != Some(Utf8Path::new(src)) {
+        return Err(anyhow!("Unable to add {dest} to the [link] table"));
     }
+    Ok(updated)
 }
 
 fn read_package<P>(path: P) -> io::Result<String>
@@ -66,10 +232,11 @@ where
 mod tests {
     use super::*;
     mod dot_package {
-        use camino::Utf8PathBuf;
+        use camino::{Utf8Path, Utf8PathBuf};
         use test_utils::TestResult;
 
-        use super::parse_package;
+        use super::{insert_link, parse_package, DotError};
+        use crate::plan::links::LinkMode;
 
         const EXAMPLE_PACKAGE: &str = r#"
         [package]
@@ -81,6 +248,53 @@ mod tests {
         "shell/gitconfig" = "~/.gitconfig"
         "#;
 
+        const CONDITIONAL_PACKAGE: &str = r#"
+        [package]
+        name = "my_package_name"
+        authors = [ "Michael Mullins" ]
+
+        [target.'cfg(os = "linux")'.link]
+        "~/.xprofile" = "x/xprofile"
+
+        [host.workstation.link]
+        "~/.ssh/config" = "ssh/config"
+        "#;
+
+        const PROFILE_PACKAGE: &str = r#"
+        [package]
+        name = "my_package_name"
+        authors = [ "Michael Mullins" ]
+
+        [link]
+        "~/.bashrc" = "shell/bashrc"
+        "~/.gitconfig" = "git/gitconfig"
+
+        [profile.minimal]
+        links = [ "~/.bashrc" ]
+        "#;
+
+        const TEMPLATE_PACKAGE: &str = r#"
+        [package]
+        name = "my_package_name"
+        authors = [ "Michael Mullins" ]
+
+        [template]
+        "~/.gitconfig" = "git/gitconfig"
+
+        [variables]
+        email = "me@example.com"
+        "#;
+
+        const COPY_PACKAGE: &str = r#"
+        [package]
+        name = "my_package_name"
+        authors = [ "Michael Mullins" ]
+
+        [link]
+        "~/.bashrc" = "shell/bashrc"
+        "~/.config/app/settings.json" = { src = "app/settings.json", mode = "copy" }
+        "#;
+
         const BAD_PACKAGE: &str = r#"
         [package]
         name = "my_package_name"
@@ -99,12 +313,109 @@ mod tests {
         fn it_should_parse_the_list_of_links() -> TestResult {
             let result = parse_package(EXAMPLE_PACKAGE)?;
             let key = Utf8PathBuf::from("shell/bashrc");
-            let value = result.link.get(&key);
-            let expected = Utf8PathBuf::from("~/.bashrc");
-            assert_eq!(value, Some(&expected));
+            let value = result.link.get(&key).map(|link| link.src());
+            let expected = Utf8Path::new("~/.bashrc");
+            assert_eq!(value, Some(expected));
+            Ok(())
+        }
+
+        #[test]
+        fn it_should_parse_conditional_links() -> TestResult {
+            let result = parse_package(CONDITIONAL_PACKAGE)?;
+            assert!(result.link.is_empty());
+
+            let target = &result.target[r#"cfg(os = "linux")"#];
+            let value = target.link.get(&Utf8PathBuf::from("~/.xprofile"));
+            assert_eq!(
+                value.map(|link| link.src()),
+                Some(Utf8Path::new("x/xprofile"))
+            );
+
+            let host = &result.host["workstation"];
+            let value = host.link.get(&Utf8PathBuf::from("~/.ssh/config"));
+            assert_eq!(
+                value.map(|link| link.src()),
+                Some(Utf8Path::new("ssh/config"))
+            );
             Ok(())
         }
 
+        #[test]
+        fn it_should_parse_profiles() -> TestResult {
+            let result = parse_package(PROFILE_PACKAGE)?;
+            let profile = &result.profile["minimal"];
+            assert_eq!(profile.links, vec![Utf8PathBuf::from("~/.bashrc")]);
+            Ok(())
+        }
+
+        #[test]
+        fn it_should_parse_templates_and_variables() -> TestResult {
+            let result = parse_package(TEMPLATE_PACKAGE)?;
+            let value = result.template.get(&Utf8PathBuf::from("~/.gitconfig"));
+            assert_eq!(
+                value.map(|link| link.src()),
+                Some(Utf8Path::new("git/gitconfig"))
+            );
+            assert_eq!(result.variables["email"], "me@example.com");
+            Ok(())
+        }
+
+        #[test]
+        fn it_should_parse_link_modes() -> TestResult {
+            let result = parse_package(COPY_PACKAGE)?;
+            let bashrc = &result.link[Utf8Path::new("~/.bashrc")];
+            assert_eq!(bashrc.mode(), LinkMode::Symlink);
+
+            let settings = &result.link[Utf8Path::new("~/.config/app/settings.json")];
+            assert_eq!(settings.src(), "app/settings.json");
+            assert_eq!(settings.mode(), LinkMode::Copy);
+            Ok(())
+        }
+
+        #[test]
+        fn it_should_insert_links_without_changing_the_rest_of_the_file() -> TestResult {
+            let contents = "# my dot\n[package]\nname = \"example_dot\"\nauthors = []\n\n[link]\n\"~/.bashrc\" = \"shell/bashrc\" # bash\n\n[variables]\nemail = \"me@example.com\"\n";
+            let result = insert_link(contents, "~/.tmux.conf", "tmux.conf")?;
+            assert_eq!(
+                result,
+                "# my dot\n[package]\nname = \"example_dot\"\nauthors = []\n\n[link]\n\"~/.bashrc\" = \"shell/bashrc\" # bash\n\"~/.tmux.conf\" = \"tmux.conf\"\n\n[variables]\nemail = \"me@example.com\"\n"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn it_should_add_a_link_table_if_there_isnt_one() -> TestResult {
+            let contents = "[package]\nname = \"example_dot\"\nauthors = []\n";
+            let result = insert_link(contents, "~/.tmux.conf", "tmux.conf")?;
+            assert_eq!(
+                result,
+                "[package]\nname = \"example_dot\"\nauthors = []\n\n[link]\n\"~/.tmux.conf\" = \"tmux.conf\"\n"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn it_should_report_where_parse_errors_happened() {
+            let err = parse_package("[package]\nname = \"example_dot\"\nauthors = [\n")
+                .map(|_| ())
+                .map_err(DotError::from)
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                DotError::Parse {
+                    span: Some((4, 1)),
+                    ..
+                }
+            ));
+            assert_eq!(err.to_string(), "Dot.toml:4:1: unexpected eof encountered");
+        }
+
+        #[test]
+        fn it_should_fail_to_insert_a_duplicate_link() {
+            let contents = "[package]\nname = \"example_dot\"\nauthors = []\n\n[link]\n\"~/.tmux.conf\" = \"tmux\"\n";
+            assert!(insert_link(contents, "~/.tmux.conf", "tmux.conf").is_err());
+        }
+
         #[test]
         #[should_panic(expected = "kind: Wanted")]
         fn it_should_throw_an_error_when_parse_errors_fail() {
@@ -1,53 +1,118 @@
-use crate::dot_package::{DotPackageConfig, DotPackageMeta};
-use crate::plan::links::Link;
-use crate::plan::resolve::{resolve, ResolvedLink};
+use crate::condition::{Condition, Key, Machine};
+use crate::config::Config;
+use crate::dot_package::{DotError, DotPackageConfig, DotPackageMeta};
+use crate::error::DotsError;
+use crate::local::{LocalDots, LocalMode};
+use crate::lockfile::LockedDot;
+use crate::pin::{Pin, Pins};
+use crate::plan::links::{Link, SkippedLink};
+use crate::plan::resolve::{resolve, resolve_template, ResolvedLink};
+use crate::profile::SelectedProfile;
+use crate::template;
 use crate::utils::{self, fs::home};
 use anyhow::Result;
 use camino::{Utf8Path, Utf8PathBuf};
-use std::{env, fs, io, process};
+use std::{env, fs, io};
 use tempfile::tempdir;
 
 #[derive(PartialEq, Eq)]
 pub struct Dot {
     pub package: DotPackageMeta,
     pub links: Vec<ResolvedLink>,
+    /// Links that don't apply to the current machine
+    pub skipped: Vec<SkippedLink>,
+    /// The names of the profiles defined in Dot.toml
+    pub profiles: Vec<String>,
     pub path: Utf8PathBuf,
+    /// Whether the dot is used in place from a local directory instead of living in the dots root
+    pub in_place: bool,
 }
 
 impl Dot {
-    pub fn new<P>(path: P) -> Result<Dot>
+    pub fn new<P>(path: P, env: &Environment) -> Result<Dot, DotError>
     where
         P: AsRef<Utf8Path>,
     {
         let path = path.as_ref();
         let config = DotPackageConfig::read_and_parse(path)?;
-        let links = config
-            .link
-            .iter()
-            .map(|(dest, src)| resolve(path, Link::new(src, dest)))
-            .collect();
+
+        let mut tables = vec![(None, &config.link, false), (None, &config.template, true)];
+        for (key, table) in &config.target {
+            let condition = Condition::parse(key)
+                .map_err(|err| DotError::Invalid(format!("invalid target {key}: {err}")))?;
+            tables.push((Some(condition), &table.link, false));
+        }
+        for (hostname, table) in &config.host {
+            let condition = Condition::Is(Key::Hostname, hostname.clone());
+            tables.push((Some(condition), &table.link, false));
+        }
+
+        /* Dots that don't define the selected profile link everything */
+        let profile = env
+            .profile()
+            .and_then(|name| config.profile.get(name).map(|profile| (name, profile)));
+        if let Some((name, profile)) = profile {
+            for dest in &profile.links {
+                if !tables.iter().any(|(_, table, _)| table.contains_key(dest)) {
+                    return Err(DotError::Invalid(format!(
+                        "profile {name} enables {dest}, but no link has that destination"
+                    )));
+                }
+            }
+        }
+
+        let name = &config.package.name;
+        let variables = template::variables(name, &config.variables, env);
+
+        let mut links = vec![];
+        let mut skipped = vec![];
+        for (condition, table, is_template) in tables {
+            for (dest, entry) in table {
+                let src = entry.src();
+                let link = Link::new(src, dest).with_mode(entry.mode());
+                match (&condition, profile) {
+                    (Some(condition), _) if !condition.matches(env.machine()) => {
+                        skipped.push(SkippedLink {
+                            link,
+                            reason: format!("requires {condition}"),
+                        })
+                    }
+                    (_, Some((name, profile))) if !profile.links.contains(dest) => {
+                        skipped.push(SkippedLink {
+                            link,
+                            reason: format!("not in profile {name}"),
+                        })
+                    }
+                    _ if is_template => {
+                        let output = env.generated_path().join(name).join(src);
+                        links.push(resolve_template(path, link, output, &variables))
+                    }
+                    _ => links.push(resolve(path, link)),
+                }
+            }
+        }
 
         Ok(Dot {
             package: config.package,
             links,
+            skipped,
+            profiles: config.profile.keys().cloned().collect(),
             path: path.to_path_buf(),
+            in_place: false,
         })
     }
 
-    pub fn origin(&self) -> String {
-        match env::set_current_dir(self.path.clone()) {
-            Ok(_) => utils::git::get_origin().unwrap_or_else(|err| {
-                error!(
-                    "error getting origin in git repository {}:\n{}",
-                    self.path, err
-                );
-                process::exit(1);
-            }),
-            Err(err) => {
-                error!("error changing directory to {:?}:\n{}", self.path, err);
-                process::exit(1);
-            }
+    /** Whether the dot is a git checkout that can be updated and locked */
+    pub fn is_git(&self) -> bool {
+        utils::git::is_repo(&self.path)
+    }
+
+    /** The git origin of the dot, or `None` if it isn't a git checkout or doesn't have one */
+    pub fn origin(&self) -> Option<String> {
+        if !self.is_git() {
+            return None;
         }
+        utils::git::get_origin(&self.path).ok()
     }
 }
 
@@ -61,33 +126,115 @@ impl Ord for Dot {
 
 impl PartialOrd for Dot {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let name = &self.package.name;
-        let other_name = &other.package.name;
-        Some(name.cmp(other_name))
+        Some(self.cmp(other))
     }
 }
 
 pub struct Environment {
     root: Utf8PathBuf,
+    footprint_path: Utf8PathBuf,
+    machine: Machine,
+    profile: Option<String>,
+    config: Config,
+    /// The names and directories of dots that are used in place, as they were when the command
+    /// started
+    local_dots: Vec<(String, Utf8PathBuf)>,
 }
 
-impl Default for Environment {
-    fn default() -> Self {
-        Self {
-            root: home().join(".dots"),
+/**
+ * Picks the root directory for dots. `DOTS_HOME` always wins. Otherwise `$XDG_DATA_HOME/dots` is
+ * used, unless there's already an install at `~/.dots` that we shouldn't abandon.
+ */
+fn default_root(
+    dots_home: Option<String>,
+    xdg_data_home: Option<String>,
+    home: &Utf8Path,
+) -> Utf8PathBuf {
+    let legacy_root = home.join(".dots");
+    match (dots_home, xdg_data_home) {
+        (Some(root), _) if !root.is_empty() => utils::fs::absolute(root),
+        (_, Some(data)) if !data.is_empty() && !legacy_root.exists() => {
+            utils::fs::absolute(data).join("dots")
         }
+        _ => legacy_root,
     }
 }
 
 impl Environment {
-    pub fn new() -> Self {
-        Environment::default()
+    /**
+     * Finds the root that dots are installed to. The given root wins, then the `DOTS_HOME`
+     * environment variable, then the root in the config file. The footprint can be moved out of
+     * the root by passing its path or with `DOTS_FOOTPRINT`.
+     */
+    pub fn new(config: Config, root: Option<&str>, footprint: Option<&str>) -> Self {
+        let dots_home = root
+            .map(String::from)
+            .or_else(|| env::var("DOTS_HOME").ok())
+            .filter(|root| !root.is_empty())
+            .or_else(|| config.root.clone());
+        let root = default_root(dots_home, env::var("XDG_DATA_HOME").ok(), &home());
+        let footprint = footprint
+            .map(String::from)
+            .or_else(|| env::var("DOTS_FOOTPRINT").ok());
+        let footprint_path = match footprint {
+            Some(path) if !path.is_empty() => utils::fs::absolute(path),
+            _ => root.join("dot-footprint.toml"),
+        };
+
+        let mut machine = Machine::current();
+        if let (Err(_), Some(hostname)) = (env::var("DOTS_HOSTNAME"), &config.machine.hostname) {
+            machine.hostname = hostname.clone();
+        }
+
+        let mut env = Self {
+            root,
+            footprint_path,
+            machine,
+            profile: None,
+            config,
+            local_dots: vec![],
+        };
+        env.local_dots = LocalDots::read(&env)
+            .iter()
+            .map(|(name, path)| (name.clone(), path.clone()))
+            .collect();
+        env.profile = SelectedProfile::read(env.profile_path())
+            .name
+            .or_else(|| env.config.install.profile.clone());
+        env
     }
 
     pub fn root(&self) -> Utf8PathBuf {
         self.root.clone()
     }
 
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /** The defaults loaded from the config file */
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /** The profile whose links should be installed. All links are installed when this is `None` */
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /** Switches to the given profile for this run. Call `save_profile` to remember it. */
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
+
+    /** Remembers the current profile so that future runs install the same links */
+    pub fn save_profile(&self) -> Result<()> {
+        let selected = SelectedProfile {
+            name: self.profile.clone(),
+        };
+        selected.save(self.profile_path())
+    }
+
     pub fn path<P>(&self, path: P) -> Utf8PathBuf
     where
         P: AsRef<Utf8Path>,
@@ -95,134 +242,552 @@ impl Environment {
         self.root.join(path)
     }
 
+    /** Whether the given path belongs to an installed dot, including dots used in place */
+    pub fn is_dot_path(&self, path: &Utf8Path) -> bool {
+        path.starts_with(&self.root) || self.local_dots.iter().any(|(_, dir)| path.starts_with(dir))
+    }
+
+    /**
+     * The name of the dot the given path belongs to, including rendered templates, judging only by
+     * where the path lives.
+     */
+    pub fn dot_name_of(&self, path: &Utf8Path) -> Option<String> {
+        if let Some((name, _)) = self
+            .local_dots
+            .iter()
+            .find(|(_, dir)| path.starts_with(dir))
+        {
+            return Some(name.clone());
+        }
+        let relative = path
+            .strip_prefix(self.generated_path())
+            .or_else(|_| path.strip_prefix(&self.root))
+            .ok()?;
+        match relative.components().next()?.as_str() {
+            name if name.starts_with('.') => None,
+            name => Some(name.to_string()),
+        }
+    }
+
     pub fn package_path(&self, dot: &Dot) -> Utf8PathBuf {
         self.path(&dot.package.name)
     }
 
     pub fn footprint_path(&self) -> Utf8PathBuf {
-        self.path("dot-footprint.toml")
+        self.footprint_path.clone()
+    }
+
+    /** The lockfile always lives next to the footprint */
+    pub fn lockfile_path(&self) -> Utf8PathBuf {
+        self.footprint_path().with_file_name("dots.lock")
+    }
+
+    /** Records the changes of an install that's in progress. It always lives next to the footprint */
+    pub fn journal_path(&self) -> Utf8PathBuf {
+        self.footprint_path().with_file_name("dot-journal.toml")
+    }
+
+    /** Held by the command that's currently changing the root */
+    pub fn root_lock_path(&self) -> Utf8PathBuf {
+        self.path("dot-root.lock")
+    }
+
+    pub fn pins_path(&self) -> Utf8PathBuf {
+        self.path("dot-pins.toml")
+    }
+
+    /** Records dots that are used in place from a local directory */
+    pub fn local_dots_path(&self) -> Utf8PathBuf {
+        self.path("dot-locals.toml")
+    }
+
+    pub fn profile_path(&self) -> Utf8PathBuf {
+        self.path("dot-profile.toml")
+    }
+
+    pub fn backups_path(&self) -> Utf8PathBuf {
+        self.path(".backups")
+    }
+
+    /** Where rendered templates are written to */
+    pub fn generated_path(&self) -> Utf8PathBuf {
+        self.path(".generated")
+    }
+
+    /** Local, per-machine template variables that aren't tracked by any dot */
+    pub fn variables_path(&self) -> Utf8PathBuf {
+        self.path("dot-variables.toml")
     }
 }
 
-pub fn add(url: &str, overwrite: bool, env: &Environment) {
+/**
+ * Adds the dot at the given url and returns its name. Local directories that aren't git checkouts
+ * are used in place unless another [`LocalMode`] is given.
+ */
+pub fn add(
+    url: &str,
+    overwrite: bool,
+    pin: Option<Pin>,
+    local: Option<LocalMode>,
+    env: &Environment,
+) -> Result<String, DotsError> {
+    let url = env.config().registry.get(url).map_or(url, String::as_str);
+    let local = local.or_else(|| {
+        let path = Utf8Path::new(url);
+        (path.is_dir() && !utils::git::is_repo(path)).then_some(LocalMode::Link)
+    });
+    if let Some(mode) = local {
+        return add_local(url, overwrite, mode, env);
+    }
+
     info!("Adding {url}");
-    let tmp = tempdir().expect("Unable to create temporary directory");
+    let tmp =
+        tempdir().map_err(|err| DotsError::io("Unable to create temporary directory", err))?;
     let tmp_path = Utf8Path::from_path(tmp.path()).unwrap().join("dot");
 
-    info!("Cloning...");
-    utils::git::clone(url, &tmp_path).unwrap_or_else(|error| {
-        error!("Unable to clone dot\n{}", error);
-        process::exit(1)
-    });
+    clone(url, &tmp_path, &pin)?;
 
-    let dot = match Dot::new(&tmp_path) {
-        Ok(dot) => dot,
-        Err(_) => {
-            error!("Repo does not appear to be a Dot");
-            process::exit(1);
-        }
-    };
+    let dot = Dot::new(&tmp_path, env)
+        .map_err(|err| DotsError::dot("Repo does not appear to be a Dot:", err))?;
 
     let target_dir = env.package_path(&dot);
+    let mut pins = Pins::read(env);
+    let mut locals = LocalDots::read(env);
+    let mut pin = pin;
 
-    if target_dir.exists() {
-        if overwrite {
-            warn!("Overwriting pre-existing Dot\n{}", target_dir);
-            utils::fs::clean(&target_dir);
-        } else {
-            error!(
-                "A Dot named {} is already installed. Aborting.",
+    if target_dir.exists() || locals.get(&dot.package.name).is_some() {
+        if !overwrite {
+            return Err(DotsError::Invalid(format!(
+                "A Dot named {} is already installed. Aborting.\npass --overwrite to overwrite the pre-existing Dot",
                 env.package_path(&dot)
-            );
-            error!("pass --overwrite to overwrite the pre-existing Dot");
-            process::exit(1);
+            )));
+        }
+        if let (None, Some(existing)) = (&pin, pins.get(&dot.package.name)) {
+            info!("Keeping existing pin to {existing}");
+            utils::fs::clean(&tmp_path);
+            clone(url, &tmp_path, &Some(existing.clone()))?;
+            pin = Some(existing.clone());
         }
+        warn!("Overwriting pre-existing Dot\n{}", target_dir);
+        utils::fs::clean(&target_dir);
+        locals.set(&dot.package.name, None);
     }
 
-    fs::create_dir_all(&target_dir).expect("Creating package root");
     info!("Copying to {}", target_dir);
-    match fs::rename(&tmp_path, &target_dir) {
-        Ok(_) => info!("Done!"),
-        Err(err) => error!("Error adding dot. Copy failed due to the following error:\n  {err}"),
+    fs::create_dir_all(&env.root)
+        .and_then(|_| utils::fs::move_path(&tmp_path, &target_dir))
+        .map_err(|err| {
+            DotsError::io(
+                "Error adding dot. Copy failed due to the following error:",
+                err,
+            )
+        })?;
+
+    pins.set(&dot.package.name, pin);
+    pins.save(env)
+        .map_err(|err| DotsError::footprint("Unable to save pinned ref:", err))?;
+    locals
+        .save(env)
+        .map_err(|err| DotsError::footprint("Unable to save local dots:", err))?;
+    info!("Done!");
+    Ok(dot.package.name)
+}
+
+/** Adds a dot from a local directory without going through git and returns its name */
+fn add_local(
+    path: &str,
+    overwrite: bool,
+    mode: LocalMode,
+    env: &Environment,
+) -> Result<String, DotsError> {
+    let path = utils::fs::absolute(path);
+    info!("Adding local directory {path}");
+
+    let dot = Dot::new(&path, env)
+        .map_err(|err| DotsError::dot("Directory does not appear to be a Dot:", err))?;
+
+    let name = &dot.package.name;
+    let target_dir = env.package_path(&dot);
+    let mut pins = Pins::read(env);
+    let mut locals = LocalDots::read(env);
+
+    if target_dir.exists() || locals.get(name).is_some() {
+        if !overwrite {
+            return Err(DotsError::Invalid(format!(
+                "A Dot named {name} is already installed. Aborting.\npass --overwrite to overwrite the pre-existing Dot"
+            )));
+        }
+        warn!("Overwriting pre-existing Dot\n{}", target_dir);
+        utils::fs::clean(&target_dir);
+        locals.set(name, None);
+        pins.set(name, None);
+    }
+
+    match mode {
+        LocalMode::Link => {
+            info!("Using {name} in place");
+            locals.set(name, Some(&path));
+        }
+        LocalMode::Copy => {
+            info!("Copying to {}", target_dir);
+            utils::fs::copy_dir_excluding(&path, &target_dir, &[".git"]).map_err(|err| {
+                DotsError::io(
+                    "Error adding dot. Copy failed due to the following error:",
+                    err,
+                )
+            })?;
+        }
+    }
+
+    pins.save(env)
+        .and_then(|_| locals.save(env))
+        .map_err(|err| DotsError::footprint("Unable to save local dots:", err))?;
+    info!("Done!");
+    Ok(dot.package.name)
+}
+
+fn clone(url: &str, dest: &Utf8Path, pin: &Option<Pin>) -> Result<(), DotsError> {
+    let result = match pin {
+        Some(pin) => {
+            info!("Cloning {pin}...");
+            pin.clone_repo(url, dest)
+        }
+        None => {
+            info!("Cloning...");
+            utils::git::clone(url, dest)
+        }
     };
+
+    result.map_err(|err| DotsError::git("Unable to clone dot", err))
 }
 
-pub fn remove(dot_name: &str, env: &Environment) -> Result<()> {
-    match find(dot_name, env) {
-        Some(dot) => {
-            fs::remove_dir_all(&dot.path).unwrap_or_else(|err| {
-                error!("Unable to remove dot directory:\n{}", dot.path);
-                error!("{}", err);
-                process::exit(1);
-            });
+/** Fails if the given dot has any uncommitted changes */
+pub fn check_for_local_changes(dot: &Dot) -> Result<(), DotsError> {
+    if !dot.is_git() {
+        return Ok(());
+    }
+    let name = &dot.package.name;
+    let status = utils::git::get_status(&dot.path)
+        .map_err(|err| DotsError::git(format!("Unable to get the status of {name}"), err))?;
+
+    if !status.is_empty() {
+        return Err(DotsError::Invalid(format!(
+            "{name} has local changes. Commit or discard them before updating:\n{status}"
+        )));
+    }
+    Ok(())
+}
+
+/** Fast-forwards the given dot to the latest commit of its upstream branch */
+pub fn update(dot: &Dot, env: &Environment) -> Result<(), DotsError> {
+    let name = &dot.package.name;
+    let pin = Pins::read(env).get(name).cloned();
+    if let Some(pin) = pin.as_ref().filter(|pin| !pin.is_movable()) {
+        info!("{name} is pinned to {pin}, skipping update");
+        return Ok(());
+    }
+    if !dot.is_git() {
+        info!("{name} is not a git checkout, skipping update");
+        return Ok(());
+    }
+    /* `install --locked` leaves the checkout at the locked commit instead of on a branch */
+    if utils::git::get_branch(&dot.path).is_ok_and(|branch| branch == "HEAD") {
+        let branch = match pin {
+            Some(Pin::Branch(branch)) => branch,
+            _ => utils::git::default_branch(&dot.path).map_err(|err| {
+                DotsError::git(
+                    format!("Unable to find the branch to update {name} from"),
+                    err,
+                )
+            })?,
+        };
+        info!("{name} is checked out at a locked commit, switching back to {branch}");
+        utils::git::switch(&dot.path, &branch)
+            .map_err(|err| DotsError::git(format!("Unable to check out {branch}"), err))?;
+    }
+    info!("Updating {name}...");
+    utils::git::pull(&dot.path)
+        .map_err(|err| DotsError::git(format!("Unable to update {name}"), err))?;
+    Ok(())
+}
+
+/** Checks out the commit recorded in the lockfile, adding the dot first if it is missing */
+pub fn checkout_locked(locked: &LockedDot, env: &Environment) -> Result<(), DotsError> {
+    let name = &locked.name;
+    let dot = match find(name, env)? {
+        Some(dot) => dot,
+        None => {
+            add(&locked.origin, false, None, None, env)?;
+            find(name, env)?.ok_or_else(|| {
+                DotsError::NotFound(format!(
+                    "{} does not contain a dot named {name}",
+                    locked.origin
+                ))
+            })?
+        }
+    };
+
+    if let Ok(commit) = utils::git::rev_parse(&dot.path, "HEAD") {
+        if commit == locked.commit {
+            return Ok(());
+        }
+    }
+
+    check_for_local_changes(&dot)?;
+
+    info!("Checking out {name} at {}...", locked.commit);
+    if utils::git::rev_parse(&dot.path, &locked.commit).is_err() {
+        utils::git::fetch(&dot.path)
+            .map_err(|err| DotsError::git(format!("Unable to fetch {name}"), err))?;
+    }
+    utils::git::checkout(&dot.path, &locked.commit).map_err(|err| {
+        DotsError::git(
+            format!("Unable to check out {name} at {}", locked.commit),
+            err,
+        )
+    })?;
+    Ok(())
+}
+
+/**
+ * Reports what [`checkout_locked`] would change without changing anything. Dry runs plan against
+ * the checkouts as they are.
+ */
+pub fn preview_locked(locked: &LockedDot, env: &Environment) -> Result<(), DotsError> {
+    let name = &locked.name;
+    let Some(dot) = find(name, env)? else {
+        info!(
+            "Would add {name} from {} at {}",
+            locked.origin, locked.commit
+        );
+        return Ok(());
+    };
+    match utils::git::rev_parse(&dot.path, "HEAD") {
+        Ok(commit) if commit == locked.commit => {}
+        _ => info!("Would check out {name} at {}", locked.commit),
+    }
+    Ok(())
+}
+
+pub fn remove(dot_name: &str, env: &Environment) -> Result<(), DotsError> {
+    /* broken dots can be removed by the name they were installed under */
+    let (dots, broken) = load_all(env)?;
+    let found = match dots.into_iter().find(|dot| dot.package.name == dot_name) {
+        Some(dot) => Some((dot.path, dot.in_place)),
+        None => broken
+            .into_iter()
+            .find(|dot| dot.name == dot_name)
+            .map(|dot| (dot.path, dot.in_place)),
+    };
+
+    match found {
+        Some((path, true)) => {
+            let mut locals = LocalDots::read(env);
+            locals.set(dot_name, None);
+            locals
+                .save(env)
+                .map_err(|err| DotsError::footprint("Unable to save local dots:", err))?;
+            info!("Stopped using {dot_name}, its files were left in {path}");
+        }
+        Some((path, false)) => {
+            fs::remove_dir_all(&path).map_err(|err| {
+                DotsError::io(format!("Unable to remove dot directory:\n{path}"), err)
+            })?;
+            let mut pins = Pins::read(env);
+            pins.set(dot_name, None);
+            pins.save(env)
+                .map_err(|err| DotsError::footprint("Unable to save pinned ref:", err))?;
         }
         None => {
-            error!(
-                "Unable to find an installed dot with the name: {}",
-                dot_name
-            );
-            process::exit(1);
+            return Err(DotsError::NotFound(format!(
+                "Unable to find an installed dot with the name: {dot_name}"
+            )));
         }
     }
     Ok(())
 }
 
-pub fn find(dot_name: &str, env: &Environment) -> Option<Dot> {
-    find_all(env)
+pub fn find(dot_name: &str, env: &Environment) -> Result<Option<Dot>, DotsError> {
+    Ok(find_all(env)?
         .into_iter()
-        .find(|dot| dot.package.name == dot_name)
+        .find(|dot| dot.package.name == dot_name))
 }
 
-pub fn find_all(env: &Environment) -> Vec<Dot> {
-    let dir = match env.root.read_dir() {
-        Ok(read_dir) => read_dir,
-        Err(err) => {
-            use io::ErrorKind as Kind;
-            match err.kind() {
-                Kind::NotFound => return vec![],
-                Kind::PermissionDenied => {
-                    error!("Unable access dots directory:\n{}", err);
-                    process::exit(1);
-                }
-                _ => {
-                    error!("Error while accessing dots directory:\n{}", err);
-                    process::exit(1);
-                }
+/// A directory that should contain a dot, whether or not its Dot.toml can be loaded
+pub struct DotDir {
+    /// The name the dot is installed under: its directory name in the root, or its local dots key
+    pub name: String,
+    pub path: Utf8PathBuf,
+    pub in_place: bool,
+}
+
+/// A dot that is installed but can't be loaded, usually because of a mistake in its Dot.toml
+#[derive(Debug)]
+pub struct BrokenDot {
+    /// The name the dot was installed under
+    pub name: String,
+    pub path: Utf8PathBuf,
+    pub in_place: bool,
+    pub error: DotError,
+}
+
+impl BrokenDot {
+    /**
+     * Whether the given path belongs to this dot, including its rendered templates. Links to
+     * these paths have to be left alone until the dot can be loaded again.
+     */
+    pub fn owns(&self, path: &Utf8Path, env: &Environment) -> bool {
+        path.starts_with(&self.path) || path.starts_with(env.generated_path().join(&self.name))
+    }
+
+    pub fn output(&self) -> BrokenDotOutput<'_> {
+        let span = match &self.error {
+            DotError::Parse { span, .. } => *span,
+            _ => None,
+        };
+        BrokenDotOutput {
+            name: &self.name,
+            path: &self.path,
+            error: self.error.to_string(),
+            line: span.map(|(line, _)| line),
+            column: span.map(|(_, column)| column),
+        }
+    }
+}
+
+/// How a broken dot is described in JSON output
+#[derive(Serialize)]
+pub struct BrokenDotOutput<'a> {
+    name: &'a str,
+    path: &'a Utf8Path,
+    error: String,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+/** Every installed dot that can be loaded */
+pub fn find_all(env: &Environment) -> Result<Vec<Dot>, DotsError> {
+    Ok(load_all(env)?.0)
+}
+
+/** Loads every installed dot, collecting the dots that fail to load separately */
+pub fn load_all(env: &Environment) -> Result<(Vec<Dot>, Vec<BrokenDot>), DotsError> {
+    let mut dots = Vec::new();
+    let mut broken = Vec::new();
+    for dir in dot_dirs(env)? {
+        match Dot::new(&dir.path, env) {
+            Ok(mut dot) => {
+                dot.in_place = dir.in_place;
+                dots.push(dot)
             }
+            Err(error) => broken.push(BrokenDot {
+                name: dir.name,
+                path: dir.path,
+                in_place: dir.in_place,
+                error,
+            }),
         }
+    }
+
+    /*
+     * @todo add tests for how we sort dots when they're displayed
+     */
+    dots.sort();
+
+    Ok((dots, broken))
+}
+
+fn dots_dir_error(err: io::Error) -> DotsError {
+    match err.kind() {
+        io::ErrorKind::PermissionDenied => DotsError::io("Unable access dots directory:", err),
+        _ => DotsError::io("Error while accessing dots directory:", err),
+    }
+}
+
+/** Lists every directory in the dots root that should contain a dot, followed by the local dots */
+pub fn dot_dirs(env: &Environment) -> Result<Vec<DotDir>, DotsError> {
+    let dir = match env.root.read_dir() {
+        Ok(read_dir) => read_dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(dots_dir_error(err)),
     };
 
-    let mut dots = Vec::new();
+    let mut dirs = Vec::new();
 
     for entry in dir {
-        let path = match entry {
-            Ok(entry) => entry.path(),
-            Err(err) => match err.kind() {
-                io::ErrorKind::PermissionDenied => {
-                    error!("Unable access dots directory:\n{}", err);
-                    process::exit(1);
-                }
-                _ => {
-                    error!("Error while accessing dots directory:\n{}", err);
-                    process::exit(1);
-                }
-            },
-        };
+        let path = entry.map_err(dots_dir_error)?.path();
 
         let utf8_path = Utf8PathBuf::from_path_buf(path).expect("Error parsing path as Utf8");
         if !utf8_path.is_dir() {
             continue;
         }
 
-        if let Ok(dot) = Dot::new(utf8_path) {
-            dots.push(dot)
+        // hidden directories are reserved for dots' own bookkeeping (e.g. backups)
+        if let Some(true) = utf8_path.file_name().map(|name| name.starts_with('.')) {
+            continue;
+        }
+
+        let Some(name) = utf8_path.file_name().map(String::from) else {
+            continue;
+        };
+        dirs.push(DotDir {
+            name,
+            path: utf8_path,
+            in_place: false,
+        })
+    }
+
+    for (name, path) in LocalDots::read(env).iter() {
+        if !path.is_dir() {
+            warn!("Unable to find the local dot {name} at {path}");
+            continue;
         }
+        dirs.push(DotDir {
+            name: name.clone(),
+            path: path.to_path_buf(),
+            in_place: true,
+        })
     }
 
-    /*
-     * @todo add tests for how we sort dots when they're displayed
-     */
-    dots.sort();
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    mod default_root {
+        use crate::dots::default_root;
+        use camino::Utf8Path;
+        use std::fs;
+        use tempfile::tempdir;
+
+        #[test]
+        fn it_should_default_to_the_dots_directory_in_home() {
+            let root = default_root(None, None, Utf8Path::new("/home/user"));
+            assert_eq!(root, "/home/user/.dots");
+        }
+
+        #[test]
+        fn it_should_prefer_dots_home() {
+            let root = default_root(
+                Some(String::from("/opt/dots")),
+                Some(String::from("/home/user/.local/share")),
+                Utf8Path::new("/home/user"),
+            );
+            assert_eq!(root, "/opt/dots");
+        }
+
+        #[test]
+        fn it_should_use_xdg_data_home_if_there_is_no_existing_install() {
+            let dir = tempdir().unwrap();
+            let home = Utf8Path::from_path(dir.path()).unwrap();
+            let data = home.join(".local/share").to_string();
 
-    dots
+            let root = default_root(None, Some(data.clone()), home);
+            assert_eq!(root, home.join(".local/share/dots"));
+
+            fs::create_dir(home.join(".dots")).unwrap();
+            let root = default_root(None, Some(data), home);
+            assert_eq!(root, home.join(".dots"));
+        }
+    }
 }
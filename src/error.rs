@@ -0,0 +1,159 @@
+use camino::Utf8PathBuf;
+use std::{error::Error, fmt, io};
+use utils::git::GitError;
+
+use crate::dot_package::DotError;
+use crate::plan::PlanError;
+
+/*========*\
+*  Errors  *
+\*========*/
+
+/// Everything that can stop a command from finishing. Variants with a `message` describe what was
+/// being done when the underlying error happened, and are displayed with the error on the lines
+/// that follow.
+#[derive(Debug)]
+pub enum DotsError {
+    /// A git command failed
+    Git { message: String, source: GitError },
+    /// Reading from or writing to the fs failed
+    Io { message: String, source: io::Error },
+    /// A dot's Dot.toml couldn't be loaded
+    Dot { message: String, source: DotError },
+    /// A file that dots reads, such as a saved install plan or the config file, couldn't be parsed
+    Parse {
+        message: String,
+        source: anyhow::Error,
+    },
+    /// The install plan has problems that need to be fixed before anything can be linked
+    Plan(PlanError),
+    /// The footprint or one of the other files that dots uses to keep track of installs couldn't
+    /// be updated
+    Footprint {
+        message: String,
+        source: anyhow::Error,
+    },
+    /// Something the command was asked to work with doesn't exist
+    NotFound(String),
+    /// The command can't be carried out as it was given
+    Invalid(String),
+    /// Another dots command is already changing the given root
+    Locked(Utf8PathBuf),
+    /// The command didn't succeed, but everything there is to say about it was already printed
+    Unsuccessful,
+}
+
+pub type Result<T, E = DotsError> = std::result::Result<T, E>;
+
+impl DotsError {
+    pub fn git(message: impl Into<String>, source: GitError) -> DotsError {
+        DotsError::Git {
+            message: message.into(),
+            source,
+        }
+    }
+
+    pub fn io(message: impl Into<String>, source: io::Error) -> DotsError {
+        DotsError::Io {
+            message: message.into(),
+            source,
+        }
+    }
+
+    pub fn dot(message: impl Into<String>, source: DotError) -> DotsError {
+        DotsError::Dot {
+            message: message.into(),
+            source,
+        }
+    }
+
+    pub fn parse(message: impl Into<String>, source: impl Into<anyhow::Error>) -> DotsError {
+        DotsError::Parse {
+            message: message.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn footprint(message: impl Into<String>, source: impl Into<anyhow::Error>) -> DotsError {
+        DotsError::Footprint {
+            message: message.into(),
+            source: source.into(),
+        }
+    }
+}
+
+impl fmt::Display for DotsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DotsError::Git { message, source } => {
+                write!(f, "{message}\n{source}")
+            }
+            DotsError::Io { message, source } => {
+                write!(f, "{message}\n{source}")
+            }
+            DotsError::Dot { message, source } => {
+                write!(f, "{message}\n{source}")
+            }
+            DotsError::Parse { message, source } => {
+                write!(f, "{message}\n{source}")
+            }
+            DotsError::Plan(err) => write!(f, "{err}\nCurrently defined install would fail!"),
+            DotsError::Footprint { message, source } => {
+                write!(f, "{message}\n{source}")
+            }
+            DotsError::NotFound(message) | DotsError::Invalid(message) => write!(f, "{message}"),
+            DotsError::Locked(root) => write!(
+                f,
+                "Another dots command is already running in {root}, wait for it to finish and try again"
+            ),
+            DotsError::Unsuccessful => write!(f, "The command was unsuccessful"),
+        }
+    }
+}
+
+impl Error for DotsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DotsError::Git { source, .. } => Some(source),
+            DotsError::Io { source, .. } => Some(source),
+            DotsError::Dot { source, .. } => Some(source),
+            DotsError::Parse { source, .. } => Some(source.as_ref()),
+            DotsError::Plan(err) => Some(err),
+            DotsError::Footprint { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<PlanError> for DotsError {
+    fn from(err: PlanError) -> DotsError {
+        DotsError::Plan(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod dots_error {
+        use crate::error::DotsError;
+        use std::error::Error;
+        use std::io;
+
+        #[test]
+        fn it_should_display_the_source_after_the_message() {
+            let source = io::Error::new(io::ErrorKind::NotFound, "No such file or directory");
+            let err = DotsError::io("Unable to read Dot.toml:", source);
+            assert_eq!(
+                err.to_string(),
+                "Unable to read Dot.toml:\nNo such file or directory"
+            );
+            assert!(err.source().is_some());
+        }
+
+        #[test]
+        fn it_should_not_have_a_source_for_plain_messages() {
+            let err = DotsError::NotFound(String::from("Unable to find dot example_dot"));
+            assert_eq!(err.to_string(), "Unable to find dot example_dot");
+            assert!(err.source().is_none());
+        }
+    }
+}
@@ -1,11 +1,205 @@
-use std::collections::BTreeSet;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::SystemTime;
 
-use crate::plan::links::{Anchor, Link};
-use camino::Utf8PathBuf;
+use crate::dots::Environment;
+use crate::plan::links::{Anchor, Link, LinkMode};
+use camino::{Utf8Path, Utf8PathBuf};
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+/// The version of the footprint format written by this version of dots. Footprints written before
+/// the format was versioned are version 1.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Default, Clone)]
 pub struct Footprint {
+    /// Absolute paths to every rendered template output
+    pub generated: BTreeSet<Utf8PathBuf>,
     pub links: BTreeSet<Link>,
+    pub backups: BTreeSet<FootprintBackup>,
+    /// The content hash of every copied dotfile at the time it was copied, keyed by destination
+    pub copies: BTreeMap<Utf8PathBuf, String>,
+    /// Where each link came from and when it was installed, keyed by destination
+    pub records: BTreeMap<Utf8PathBuf, LinkRecord>,
+}
+
+impl Footprint {
+    /** Parses the contents of a footprint file, migrating footprints written by older versions */
+    pub fn parse(contents: &str) -> Result<Footprint> {
+        let file: FootprintFile = toml::from_str(contents)?;
+        match file.version {
+            /* version 1 footprints are missing the record of each link, which are all optional */
+            None | Some(1) | Some(SCHEMA_VERSION) => Ok(Footprint::from(file)),
+            Some(version) => Err(anyhow!(
+                "the footprint was written by a newer version of dots (version {version}, expected version {SCHEMA_VERSION} or below)"
+            )),
+        }
+    }
+
+    /** Serializes the footprint in the current version of the format */
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(&FootprintFile::from(self))?)
+    }
+
+    /** Returns the most recent backup taken of the given path */
+    pub fn latest_backup(&self, path: &Utf8Path) -> Option<&FootprintBackup> {
+        self.backups
+            .iter()
+            .filter(|backup| backup.path == path)
+            .max_by(|a, b| a.created.cmp(&b.created))
+    }
+
+    /**
+     * Whether the file at the given destination has been changed since dots copied it there.
+     * Returns `None` if the destination isn't a tracked copy.
+     */
+    pub fn is_copy_modified(&self, dest: &Utf8Path) -> Option<bool> {
+        let recorded = self.copies.get(dest)?;
+        match utils::fs::hash_file(dest) {
+            Ok(hash) => Some(&hash != recorded),
+            Err(_) => Some(true),
+        }
+    }
+
+    /**
+     * The name of the dot that owns the given link. Links that were tracked before owners were
+     * recorded belong to whichever dot their source lives in.
+     */
+    pub fn owner(&self, link: &Link, env: &Environment) -> Option<String> {
+        match self.records.get(&link.dest.path) {
+            Some(record) => Some(record.dot.clone()),
+            None => env.dot_name_of(&link.src.path),
+        }
+    }
+
+    /** Every tracked link owned by the given dot */
+    pub fn links_owned_by<'a>(
+        &'a self,
+        dot_name: &'a str,
+        env: &'a Environment,
+    ) -> impl Iterator<Item = &'a Link> {
+        self.links
+            .iter()
+            .filter(move |link| self.owner(link, env).as_deref() == Some(dot_name))
+    }
+}
+
+/// What dots knew about a link when it was installed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkRecord {
+    /// The name of the dot that owns the link
+    pub dot: String,
+    /// The destination exactly as it's written in the dot's Dot.toml, such as `~/.bashrc`
+    pub key: Option<String>,
+    /// When the link was installed as an RFC 3339 timestamp
+    pub installed: Option<String>,
+    /// The version of dots that installed the link
+    pub dots_version: Option<String>,
+}
+
+impl LinkRecord {
+    /** Records that the link for the given Dot.toml key was installed just now */
+    pub fn new(dot: &str, key: &str) -> LinkRecord {
+        LinkRecord {
+            dot: dot.to_string(),
+            key: Some(key.to_string()),
+            installed: Some(humantime::format_rfc3339_seconds(SystemTime::now()).to_string()),
+            dots_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }
+    }
+
+    /** A record for a link whose owner is known, but nothing else is */
+    pub fn owned_by(dot: &str) -> LinkRecord {
+        LinkRecord {
+            dot: dot.to_string(),
+            key: None,
+            installed: None,
+            dots_version: None,
+        }
+    }
+}
+
+/*=================*\
+*  Footprint Files  *
+\*=================*/
+
+/// The footprint as it's written to dot-footprint.toml
+#[derive(Serialize, Deserialize)]
+struct FootprintFile {
+    /// Missing from footprints written before the format was versioned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    /// Plain values have to be serialized before any tables
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    generated: BTreeSet<Utf8PathBuf>,
+    #[serde(default)]
+    links: Vec<FootprintLink>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    backups: BTreeSet<FootprintBackup>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    copies: BTreeMap<Utf8PathBuf, String>,
+}
+
+impl From<FootprintFile> for Footprint {
+    fn from(file: FootprintFile) -> Self {
+        let mut records = BTreeMap::new();
+        let mut links = BTreeSet::new();
+        for link in file.links {
+            if let Some(dot) = &link.dot {
+                let record = LinkRecord {
+                    dot: dot.clone(),
+                    key: link.key.clone(),
+                    installed: link.installed.clone(),
+                    dots_version: link.dots_version.clone(),
+                };
+                records.insert(link.dest.clone(), record);
+            }
+            links.insert(Link::from(link));
+        }
+        Footprint {
+            generated: file.generated,
+            links,
+            backups: file.backups,
+            copies: file.copies,
+            records,
+        }
+    }
+}
+
+impl From<&Footprint> for FootprintFile {
+    fn from(footprint: &Footprint) -> Self {
+        let links = footprint
+            .links
+            .iter()
+            .map(|link| {
+                let mut entry = FootprintLink::from(link);
+                if let Some(record) = footprint.records.get(&link.dest.path) {
+                    entry.dot = Some(record.dot.clone());
+                    entry.key = record.key.clone();
+                    entry.installed = record.installed.clone();
+                    entry.dots_version = record.dots_version.clone();
+                }
+                entry
+            })
+            .collect();
+        FootprintFile {
+            version: Some(SCHEMA_VERSION),
+            generated: footprint.generated.clone(),
+            links,
+            backups: footprint.backups.clone(),
+            copies: footprint.copies.clone(),
+        }
+    }
+}
+
+/// A file or directory that was moved out of the way to make room for a symlink
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FootprintBackup {
+    /// An absolute path to where the file originally lived
+    pub path: Utf8PathBuf,
+    /// When the backup was taken as an RFC 3339 timestamp
+    pub created: String,
+    /// An absolute path to the backed up file
+    pub backup: Utf8PathBuf,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -14,6 +208,22 @@ pub struct FootprintLink {
     pub src: Utf8PathBuf,
     /// An absolute path to the symlink
     pub dest: Utf8PathBuf,
+    /// Version 1 footprints only recorded the mode of links that weren't symlinks
+    #[serde(default)]
+    pub mode: LinkMode,
+    /// The name of the dot that owns the link. This and the fields below make up its
+    /// [`LinkRecord`], which isn't known for links tracked by older versions of dots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dot: Option<String>,
+    /// The destination exactly as it's written in the dot's Dot.toml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// When the link was installed as an RFC 3339 timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed: Option<String>,
+    /// The version of dots that installed the link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dots_version: Option<String>,
 }
 
 impl From<Link> for FootprintLink {
@@ -21,6 +231,11 @@ impl From<Link> for FootprintLink {
         FootprintLink {
             src: link.src.path,
             dest: link.dest.path,
+            mode: link.mode,
+            dot: None,
+            key: None,
+            installed: None,
+            dots_version: None,
         }
     }
 }
@@ -30,6 +245,11 @@ impl From<&Link> for FootprintLink {
         FootprintLink {
             src: link.src.path.clone(),
             dest: link.dest.path.clone(),
+            mode: link.mode,
+            dot: None,
+            key: None,
+            installed: None,
+            dots_version: None,
         }
     }
 }
@@ -39,6 +259,7 @@ impl From<FootprintLink> for Link {
         Link {
             src: Anchor::new_src(link.src),
             dest: Anchor::new_dest(link.dest),
+            mode: link.mode,
         }
     }
 }
@@ -48,6 +269,7 @@ impl From<&FootprintLink> for Link {
         Link {
             src: Anchor::new_src(link.src.clone()),
             dest: Anchor::new_dest(link.dest.clone()),
+            mode: link.mode,
         }
     }
 }
@@ -70,3 +292,56 @@ impl serde::Serialize for Link {
         footprint_link.serialize(serializer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    mod footprint {
+        use crate::footprint::{Footprint, LinkRecord};
+        use crate::plan::links::{Link, LinkMode};
+
+        #[test]
+        fn it_should_read_the_records_it_writes() {
+            let mut footprint = Footprint::default();
+            let link = Link::new("/dots/example_dot/shell/bashrc", "/home/.bashrc");
+            footprint.links.insert(link.clone());
+            footprint.records.insert(
+                link.dest.path.clone(),
+                LinkRecord::new("example_dot", "~/.bashrc"),
+            );
+
+            let contents = footprint.to_toml().unwrap();
+            assert!(contents.starts_with("version = 2\n"));
+
+            let parsed = Footprint::parse(&contents).unwrap();
+            assert!(parsed.links.contains(&link));
+            assert_eq!(parsed.records, footprint.records);
+        }
+
+        #[test]
+        fn it_should_migrate_version_1_footprints() {
+            let contents = r#"
+                [[links]]
+                src = "/dots/example_dot/shell/bashrc"
+                dest = "/home/.bashrc"
+
+                [[links]]
+                src = "/dots/example_dot/app/settings.json"
+                dest = "/home/.settings.json"
+                mode = "copy"
+            "#;
+
+            let footprint = Footprint::parse(contents).unwrap();
+            assert_eq!(footprint.links.len(), 2);
+            assert!(footprint
+                .links
+                .iter()
+                .any(|link| link.mode == LinkMode::Copy));
+            assert!(footprint.records.is_empty());
+        }
+
+        #[test]
+        fn it_should_refuse_footprints_from_newer_versions() {
+            assert!(Footprint::parse("version = 3\nlinks = []\n").is_err());
+        }
+    }
+}
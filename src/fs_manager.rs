@@ -1,23 +1,89 @@
-use anyhow::Result;
-use camino::Utf8PathBuf;
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
 use std::{fs, io, os::unix};
+use utils::fs::{hash_file, home, move_path, write_atomic};
 
-use crate::{dots::Environment, footprint::Footprint, plan::links::Link};
+use crate::{
+    dots::{BrokenDot, Environment},
+    error::DotsError,
+    footprint::{Footprint, FootprintBackup, LinkRecord},
+    plan::{
+        journal::Journal,
+        links::{Link, LinkMode},
+    },
+    template::RenderedTemplate,
+};
 
 pub struct FSManager {
     footprint_path: Utf8PathBuf,
     footprint: Footprint,
+    journal_path: Utf8PathBuf,
+    backups_path: Utf8PathBuf,
+    generated_path: Utf8PathBuf,
+    /// Whether changes to the footprint are held in memory until [`FSManager::save`] is called
+    deferred: bool,
+    /// Whether the footprint has changes that haven't been written yet
+    unsaved: bool,
 }
 
 impl FSManager {
-    pub fn init(env: &Environment) -> FSManager {
+    /**
+     * Loads the footprint of the given environment. A footprint that can't be parsed is an error
+     * rather than a fresh start, since saving over it would forget every link it tracks.
+     */
+    pub fn init(env: &Environment) -> Result<FSManager, DotsError> {
         let footprint_path = env.footprint_path();
-        let footprint = FSManager::read_and_parse_footprint(&footprint_path);
+        let footprint = FSManager::read_and_parse_footprint(&footprint_path)
+            .map_err(|err| DotsError::footprint("Unable to load the footprint:", err))?;
+        Ok(FSManager::with_footprint(env, footprint))
+    }
+
+    /**
+     * The same as [`FSManager::init`], but starts from an empty footprint if the footprint can't
+     * be read. Only meant for `dots doctor`, which moves unreadable footprints out of the way.
+     */
+    pub fn init_unchecked(env: &Environment) -> FSManager {
+        let footprint =
+            FSManager::read_and_parse_footprint(&env.footprint_path()).unwrap_or_default();
+        FSManager::with_footprint(env, footprint)
+    }
 
+    fn with_footprint(env: &Environment, footprint: Footprint) -> FSManager {
         FSManager {
-            footprint_path,
+            footprint_path: env.footprint_path(),
             footprint,
+            journal_path: env.journal_path(),
+            backups_path: env.backups_path(),
+            generated_path: env.generated_path(),
+            deferred: false,
+            unsaved: false,
+        }
+    }
+
+    pub fn footprint(&self) -> &Footprint {
+        &self.footprint
+    }
+
+    /** Where the journal of an install in progress is kept */
+    pub fn journal_path(&self) -> &Utf8Path {
+        &self.journal_path
+    }
+
+    /**
+     * Holds every change to the footprint in memory until [`FSManager::save`] is called, instead
+     * of rewriting the footprint after each one.
+     */
+    pub fn defer_saves(&mut self) {
+        self.deferred = true;
+    }
+
+    /** Writes any changes held back by [`FSManager::defer_saves`] and goes back to saving them */
+    pub fn save(&mut self) -> Result<()> {
+        self.deferred = false;
+        if self.unsaved {
+            self.save_footprint()?;
         }
+        Ok(())
     }
 
     /**
@@ -27,8 +93,20 @@ impl FSManager {
      * 2. removes any footprint links that DO have corresponding symlinks on the fs, but those symlinks
      *    do no point to the correct source.
      * 3. removes the symlink for footprint links that DO NOT have corresponding links in any Dot.toml
+     *
+     * Links that belong to a broken dot are never touched, since there's no way to tell which of
+     * them are still valid until its Dot.toml can be loaded again. When `only` is given, links
+     * owned by any other dot are left alone as well. Every removal is recorded in the given
+     * journal, so that it can be rolled back.
      */
-    pub fn clean(&mut self, valid_links: &Vec<Link>, env: &Environment) -> Result<()> {
+    pub fn clean(
+        &mut self,
+        journal: &mut Journal,
+        valid_links: &Vec<Link>,
+        broken: &[BrokenDot],
+        only: Option<&[String]>,
+        env: &Environment,
+    ) -> Result<()> {
         let original_footprint = self.footprint.clone();
         debug!("VALID LINKS");
         for link in valid_links {
@@ -40,18 +118,30 @@ impl FSManager {
             debug!("  {link:?}");
             debug!("    link is on fs: {}", link.exists());
             debug!("    link is in dots: {}", valid_links.contains(link));
-            if !symlink_exists {
+            let owner = self.footprint.owner(link, env);
+            let is_broken = broken
+                .iter()
+                .any(|dot| owner.as_ref() == Some(&dot.name) || dot.owns(&link.src.path, env));
+            if is_broken {
+                debug!("    link belongs to a dot that can't be loaded, leaving it alone");
+            } else if !self.is_owned_by(link, only, env) {
+                debug!("    link belongs to a dot that wasn't selected, leaving it alone");
+            } else if link.mode == LinkMode::Copy {
+                self.clean_copy(journal, link, valid_links, env)?;
+            } else if link.mode == LinkMode::Hardlink {
+                self.clean_hardlink(journal, link, valid_links, env)?;
+            } else if !symlink_exists {
                 debug!("    no symlink detected, removing footprint link");
                 self.remove_footprint_link(link)?;
             } else if !link.exists() {
                 debug!("    symlink detected, but pointing to wrong dest, removing footprint link");
                 self.remove_footprint_link(link)?;
-            } else if !link.src.path.starts_with(env.root()) {
-                debug!("    symlink exists, but source is outside of dots dir, removing footprint link");
+            } else if !env.is_dot_path(&link.src.path) {
+                debug!("    symlink exists, but source is outside of every dot, removing footprint link");
                 self.remove_footprint_link(link)?;
             } else if !valid_links.contains(link) {
                 debug!("    link is on fs but is no longer present in dot files, removing symlink & footprint link");
-                self.remove_symlink(link)?;
+                journal.remove_symlink(&link.dest.path)?;
                 self.remove_footprint_link(link)?;
             } else {
                 debug!("    leaving link alone")
@@ -60,11 +150,185 @@ impl FSManager {
         Ok(())
     }
 
+    /** Whether the given link is owned by one of the given dots. Every link is when there are none */
+    fn is_owned_by(&self, link: &Link, only: Option<&[String]>, env: &Environment) -> bool {
+        let Some(names) = only else {
+            return true;
+        };
+        self.footprint
+            .owner(link, env)
+            .is_some_and(|owner| names.contains(&owner))
+    }
+
+    /**
+     * The same as [`FSManager::clean`] but for copied files. Copies that were changed since they
+     * were made are never removed, they're only forgotten.
+     */
+    fn clean_copy(
+        &mut self,
+        journal: &mut Journal,
+        link: &Link,
+        valid_links: &[Link],
+        env: &Environment,
+    ) -> Result<()> {
+        let dest = &link.dest.path;
+        if !link.exists() {
+            debug!("    no copy detected, removing footprint link");
+            self.untrack_link(link)?;
+        } else if !env.is_dot_path(&link.src.path) {
+            debug!("    copy exists, but source is outside of every dot, removing footprint link");
+            self.untrack_link(link)?;
+        } else if !valid_links.contains(link) {
+            if self.footprint.is_copy_modified(dest) == Some(false) {
+                debug!(
+                    "    copy is no longer present in dot files, removing copy & footprint link"
+                );
+                journal.remove_path(dest)?;
+            } else {
+                warn!("{dest} has been changed since it was copied, leaving it in place");
+            }
+            self.untrack_link(link)?;
+        } else {
+            debug!("    leaving copy alone")
+        }
+        Ok(())
+    }
+
+    /**
+     * The same as [`FSManager::clean`] but for hard links. A hard link stops sharing a file with
+     * its source when the source is replaced (e.g. by a git checkout). Those stale links are
+     * removed so they can be relinked, unless their contents were changed.
+     */
+    fn clean_hardlink(
+        &mut self,
+        journal: &mut Journal,
+        link: &Link,
+        valid_links: &[Link],
+        env: &Environment,
+    ) -> Result<()> {
+        let dest = &link.dest.path;
+        if !dest.is_file() || dest.is_symlink() {
+            debug!("    no hardlink detected, removing footprint link");
+            self.untrack_link(link)?;
+        } else if !link.exists() {
+            let unchanged = match (fs::read(dest), fs::read(&link.src.path)) {
+                (Ok(dest), Ok(src)) => dest == src,
+                _ => false,
+            };
+            if unchanged {
+                debug!("    hardlink no longer shares a file with its source, removing hardlink & footprint link");
+                journal.remove_path(dest)?;
+            } else {
+                warn!("{dest} no longer matches its source, leaving it in place");
+            }
+            self.untrack_link(link)?;
+        } else if !env.is_dot_path(&link.src.path) {
+            debug!(
+                "    hardlink exists, but source is outside of every dot, removing footprint link"
+            );
+            self.untrack_link(link)?;
+        } else if !valid_links.contains(link) {
+            debug!("    hardlink is no longer present in dot files, removing hardlink & footprint link");
+            journal.remove_hardlink(self, link)?;
+            self.untrack_link(link)?;
+        } else {
+            debug!("    leaving hardlink alone")
+        }
+        Ok(())
+    }
+
+    /**
+     * Removes any rendered template outputs that are no longer produced by a dot, along with any
+     * directories in the generated folder that were left empty.
+     */
+    pub fn clean_generated(
+        &mut self,
+        journal: &mut Journal,
+        valid_outputs: &[Utf8PathBuf],
+        broken: &[BrokenDot],
+        only: Option<&[String]>,
+        env: &Environment,
+    ) -> Result<()> {
+        let is_selected = |output: &Utf8PathBuf| match (only, env.dot_name_of(output)) {
+            (None, _) => true,
+            (Some(names), Some(name)) => names.contains(&name),
+            (Some(_), None) => false,
+        };
+        let stale: Vec<Utf8PathBuf> = self
+            .footprint
+            .generated
+            .iter()
+            .filter(|output| !valid_outputs.contains(output))
+            .filter(|output| !broken.iter().any(|dot| dot.owns(output, env)))
+            .filter(|output| is_selected(output))
+            .cloned()
+            .collect();
+
+        stale.iter().try_for_each(|output| {
+            debug!("removing stale template output {output}");
+            if output.exists() {
+                journal.remove_output(output)?;
+            }
+            self.prune_empty_dirs(output, &self.generated_path);
+            self.untrack_generated(output)
+        })
+    }
+
+    /** Writes a rendered template to its output path */
+    pub fn write_generated(&self, template: &RenderedTemplate) -> Result<()> {
+        if let Some(parent) = template.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&template.output, &template.contents)?;
+        Ok(())
+    }
+
+    /**
+     * Puts back the previous contents of a template output. If there were no previous contents
+     * the output is removed.
+     */
+    pub fn restore_generated(&self, path: &Utf8Path, previous: &Option<String>) -> Result<()> {
+        match previous {
+            Some(contents) => fs::write(path, contents)?,
+            None => fs::remove_file(path)?,
+        }
+        Ok(())
+    }
+
+    /** Tracks the given template output in the dot footprint */
+    pub fn track_generated(&mut self, path: &Utf8Path) -> Result<()> {
+        self.footprint.generated.insert(path.to_path_buf());
+        self.footprint_changed()
+    }
+
+    /** Removes the given template output from the dot footprint */
+    pub fn untrack_generated(&mut self, path: &Utf8Path) -> Result<()> {
+        self.footprint.generated.remove(path);
+        self.footprint_changed()
+    }
+
+    /** Removes every empty parent of the given path that lives within the given root */
+    fn prune_empty_dirs(&self, path: &Utf8Path, root: &Utf8Path) {
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(root) || fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+    }
+
     /** Removes a link from the footprint file */
     fn remove_footprint_link(&mut self, link: &Link) -> Result<()> {
         self.footprint.links.remove(link);
-        self.save_footprint()?;
-        Ok(())
+        let dest = &link.dest.path;
+        if !self
+            .footprint
+            .links
+            .iter()
+            .any(|link| &link.dest.path == dest)
+        {
+            self.footprint.records.remove(dest);
+        }
+        self.footprint_changed()
     }
 
     /**
@@ -75,29 +339,198 @@ impl FSManager {
         Ok(())
     }
 
-    /** Creates the given symlink and tracks that link in the dot footprint */
-    pub fn create_symlink(&mut self, link: &Link) -> Result<()> {
+    /** Creates the given symlink on the fs */
+    pub fn create_symlink(&self, link: &Link) -> io::Result<()> {
         unix::fs::symlink(&link.src.path, &link.dest.path)?;
+        Ok(())
+    }
+
+    /** Copies the source of the given link to its destination */
+    pub fn create_copy(&self, link: &Link) -> io::Result<()> {
+        fs::copy(&link.src.path, &link.dest.path)?;
+        Ok(())
+    }
+
+    /** Creates a hard link from the destination of the given link to its source */
+    pub fn create_hardlink(&self, link: &Link) -> io::Result<()> {
+        fs::hard_link(&link.src.path, &link.dest.path)
+    }
+
+    /** Removes the hard link made for the given link from the fs */
+    pub fn remove_hardlink(&self, link: &Link) -> io::Result<()> {
+        fs::remove_file(&link.dest.path)
+    }
+
+    /** Removes the copy made for the given link from the fs */
+    pub fn remove_copy(&self, link: &Link) -> io::Result<()> {
+        fs::remove_file(&link.dest.path)
+    }
+
+    /**
+     * Tracks the given link in the dot footprint along with where it came from. For copies, the
+     * hash of the copied file is tracked as well so that later changes to it can be detected.
+     */
+    pub fn track_link(&mut self, link: &Link, record: LinkRecord) -> Result<()> {
+        if link.mode == LinkMode::Copy {
+            let hash = hash_file(&link.dest.path)?;
+            self.footprint.copies.insert(link.dest.path.clone(), hash);
+        }
+        self.footprint
+            .records
+            .insert(link.dest.path.clone(), record);
         self.footprint.links.insert(link.clone());
-        self.save_footprint()?;
+        self.footprint_changed()
+    }
+
+    /** Removes the given link from the dot footprint */
+    pub fn untrack_link(&mut self, link: &Link) -> Result<()> {
+        if link.mode == LinkMode::Copy {
+            self.footprint.copies.remove(&link.dest.path);
+        }
+        self.remove_footprint_link(link)
+    }
 
+    /**
+     * Picks where the file or directory at the given path is backed up to, in a timestamped
+     * directory in the backups folder. Paths in the home directory keep their relative location
+     * within the backup.
+     */
+    pub fn backup_for(&self, path: &Utf8Path, timestamp: &str) -> Result<FootprintBackup> {
+        let relative = match path.strip_prefix(home()) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => match path.file_name() {
+                Some(name) => Utf8PathBuf::from(name),
+                None => return Err(anyhow!("Unable to back up {path}")),
+            },
+        };
+
+        let mut backup = self.backups_path.join(timestamp).join(&relative);
+        let mut count = 0;
+        while backup.symlink_metadata().is_ok() {
+            count += 1;
+            backup = self
+                .backups_path
+                .join(timestamp)
+                .join(format!("{relative}.{count}"));
+        }
+
+        Ok(FootprintBackup {
+            path: path.to_path_buf(),
+            created: timestamp.to_string(),
+            backup,
+        })
+    }
+
+    /** Moves the backed up file or directory into the location picked by [`FSManager::backup_for`] */
+    pub fn create_backup(&self, backup: &FootprintBackup) -> Result<()> {
+        if let Some(parent) = backup.backup.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_path(&backup.path, &backup.backup)?;
         Ok(())
     }
 
-    /** Write the current footprint to the toml file */
-    fn save_footprint(&self) -> Result<()> {
-        let contents = toml::to_string(&self.footprint)?;
-        fs::write(&self.footprint_path, contents)?;
+    /**
+     * Moves a backup back to its original location and removes any directories in the backups
+     * folder that were left empty.
+     */
+    pub fn restore_backup(&self, backup: &FootprintBackup) -> Result<()> {
+        if backup.path.symlink_metadata().is_ok() {
+            return Err(anyhow!(
+                "Unable to restore backup, {} already exists",
+                backup.path
+            ));
+        }
+
+        if let Some(parent) = backup.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_path(&backup.backup, &backup.path)?;
+        self.prune_empty_dirs(&backup.backup, &self.backups_path);
         Ok(())
     }
 
-    fn read_and_parse_footprint(footprint_path: &Utf8PathBuf) -> Footprint {
-        let Ok(string) = fs::read_to_string(footprint_path) else {
-            return Footprint::default()
+    /** Tracks the given backup in the dot footprint */
+    pub fn track_backup(&mut self, backup: &FootprintBackup) -> Result<()> {
+        self.footprint.backups.insert(backup.clone());
+        self.footprint_changed()
+    }
+
+    /** Removes the given backup from the dot footprint */
+    pub fn untrack_backup(&mut self, backup: &FootprintBackup) -> Result<()> {
+        self.footprint.backups.remove(backup);
+        self.footprint_changed()
+    }
+
+    /** Reads the raw contents of the footprint file, if there is one */
+    pub fn footprint_contents(&self) -> Option<String> {
+        fs::read_to_string(&self.footprint_path).ok()
+    }
+
+    /**
+     * Replaces the footprint file with the given raw contents, dropping any changes that haven't
+     * been saved. If no contents are given the footprint file is removed. Contents that can't be
+     * parsed are still put back as they were, since `dots doctor` repairs unreadable footprints.
+     */
+    pub fn restore_footprint(&mut self, contents: &Option<String>) -> Result<()> {
+        self.footprint = match contents {
+            Some(contents) => {
+                write_atomic(&self.footprint_path, contents)?;
+                Footprint::parse(contents).unwrap_or_default()
+            }
+            None => {
+                if self.footprint_path.exists() {
+                    fs::remove_file(&self.footprint_path)?
+                }
+                Footprint::default()
+            }
         };
-        return toml::from_str(string.as_ref()).unwrap_or_else(|err| {
-            warn!("Error parsing {footprint_path}:\n{err}");
-            Footprint::default()
-        });
+        self.deferred = false;
+        self.unsaved = false;
+        Ok(())
+    }
+
+    /** Saves the footprint, unless saves are being held back by [`FSManager::defer_saves`] */
+    fn footprint_changed(&mut self) -> Result<()> {
+        self.unsaved = true;
+        if !self.deferred {
+            self.save_footprint()?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Write the current footprint to the toml file. The footprint is written to a temporary file
+     * first and renamed into place, so it's never left half written.
+     */
+    fn save_footprint(&mut self) -> Result<()> {
+        if let Ok(existing) = fs::read_to_string(&self.footprint_path) {
+            Footprint::parse(&existing).with_context(|| {
+                format!(
+                    "Refusing to overwrite {}, it can't be parsed",
+                    self.footprint_path
+                )
+            })?;
+        }
+
+        let contents = self.footprint.to_toml()?;
+        if let Some(parent) = self.footprint_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomic(&self.footprint_path, contents)?;
+        self.unsaved = false;
+        Ok(())
+    }
+
+    fn read_and_parse_footprint(footprint_path: &Utf8Path) -> Result<Footprint> {
+        match fs::read_to_string(footprint_path) {
+            Ok(contents) => Footprint::parse(&contents).map_err(|err| {
+                anyhow!(
+                    "{footprint_path} can't be parsed, run `dots doctor --fix` to move it out of the way:\n{err}"
+                )
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Footprint::default()),
+            Err(err) => Err(anyhow!("Unable to read {footprint_path}:\n{err}")),
+        }
     }
 }
@@ -0,0 +1,40 @@
+/*!
+ * The library behind the `dots` cli. Everything the cli does is available through [`commands`],
+ * and the pieces it's built from can be used to inspect and install dots from other tools.
+ */
+extern crate anyhow;
+extern crate camino;
+extern crate tempfile;
+extern crate utils;
+#[macro_use]
+extern crate clap;
+extern crate dirs;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+
+pub mod commands;
+pub mod condition;
+pub mod config;
+pub mod doctor;
+pub mod dot_package;
+pub mod dots;
+pub mod error;
+pub mod footprint;
+pub mod fs_manager;
+pub mod local;
+pub mod lockfile;
+pub mod output;
+pub mod pin;
+pub mod plan;
+pub mod profile;
+pub mod root_lock;
+pub mod template;
+
+pub use dots::{Dot, Environment};
+pub use error::{DotsError, Result};
+pub use fs_manager::FSManager;
+pub use plan::Plan;
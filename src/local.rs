@@ -0,0 +1,70 @@
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::dots::Environment;
+
+/*============*\
+*  Local Dots  *
+\*============*/
+
+/// How a dot in a local directory should be added
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocalMode {
+    /// The directory is used in place and recorded in the local dots file
+    Link,
+    /// The directory is copied into the dots root
+    Copy,
+}
+
+/// Dots that are used in place from a local directory, keyed by dot name
+#[derive(Serialize, Deserialize, Default)]
+pub struct LocalDots {
+    #[serde(flatten)]
+    dots: BTreeMap<String, Utf8PathBuf>,
+}
+
+impl LocalDots {
+    pub fn read(env: &Environment) -> LocalDots {
+        let path = env.local_dots_path();
+        let Ok(string) = fs::read_to_string(&path) else {
+            return LocalDots::default();
+        };
+        toml::from_str(&string).unwrap_or_else(|err| {
+            warn!("Error parsing {path}:\n{err}");
+            LocalDots::default()
+        })
+    }
+
+    /** Writes the local dots file, removing the file if there are no local dots */
+    pub fn save(&self, env: &Environment) -> Result<()> {
+        let path = env.local_dots_path();
+        if self.dots.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, dot_name: &str) -> Option<&Utf8Path> {
+        self.dots.get(dot_name).map(Utf8PathBuf::as_path)
+    }
+
+    pub fn set(&mut self, dot_name: &str, path: Option<&Utf8Path>) {
+        match path {
+            Some(path) => self.dots.insert(dot_name.to_string(), path.to_path_buf()),
+            None => self.dots.remove(dot_name),
+        };
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Utf8PathBuf)> {
+        self.dots.iter()
+    }
+}
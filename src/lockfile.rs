@@ -0,0 +1,59 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use utils::git;
+
+use crate::dots::{Dot, Environment};
+
+/// Records the exact commit of every installed dot so that an install can be reproduced
+#[derive(Serialize, Deserialize, Default)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub dots: Vec<LockedDot>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LockedDot {
+    /// The package name of the dot
+    pub name: String,
+    /// The git url the dot was cloned from
+    pub origin: String,
+    /// The full SHA of the commit the dot was checked out at
+    pub commit: String,
+}
+
+impl Lockfile {
+    /** Builds a lockfile from the current checkouts of the given dots */
+    pub fn from_dots(dots: &[Dot]) -> Result<Lockfile> {
+        let mut locked = vec![];
+        for dot in dots {
+            let Some(origin) = dot.origin() else {
+                warn!(
+                    "{} is not a git checkout, leaving it out of the lockfile",
+                    dot.package.name
+                );
+                continue;
+            };
+            locked.push(LockedDot {
+                name: dot.package.name.clone(),
+                origin,
+                commit: git::rev_parse(&dot.path, "HEAD")?,
+            })
+        }
+        Ok(Lockfile { dots: locked })
+    }
+
+    pub fn read(env: &Environment) -> Result<Lockfile> {
+        let path = env.lockfile_path();
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| anyhow!("Unable to read lockfile {path}:\n{err}"))?;
+        let lockfile = toml::from_str(&contents)
+            .map_err(|err| anyhow!("Unable to parse lockfile {path}:\n{err}"))?;
+        Ok(lockfile)
+    }
+
+    pub fn save(&self, env: &Environment) -> Result<()> {
+        let contents = toml::to_string(self)?;
+        fs::write(env.lockfile_path(), contents)?;
+        Ok(())
+    }
+}
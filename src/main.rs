@@ -1,27 +1,22 @@
-extern crate anyhow;
-extern crate camino;
-extern crate tempfile;
-extern crate utils;
 #[macro_use]
 extern crate clap;
-extern crate dirs;
+extern crate dots;
 extern crate env_logger;
 #[macro_use]
 extern crate log;
-#[macro_use]
-extern crate serde_derive;
-extern crate toml;
-
-mod commands;
-mod dot_package;
-pub mod dots;
-mod footprint;
-mod fs_manager;
-pub mod plan;
 
 use std::io::Write;
 
+use std::process;
+
 use clap::Parser;
+use dots::commands;
+use dots::config::Config;
+use dots::dots::Environment;
+use dots::local::LocalMode;
+use dots::output::{ColorChoice, Format};
+use dots::pin::Pin;
+use dots::DotsError;
 use env_logger::fmt::Formatter;
 use env_logger::Builder;
 use utils::stylize::Style;
@@ -43,37 +38,217 @@ mod styles {
 struct Cli {
     #[clap(subcommand)]
     commands: Option<Commands>,
+
+    /// How output should be formatted. The json format is versioned and safe to parse in scripts.
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    format: Format,
+
+    /// The directory dots are installed to. Overrides DOTS_HOME, defaults to ~/.dots
+    #[clap(long, global = true, value_name = "PATH")]
+    root: Option<String>,
+
+    /// Where to keep track of installed links. Overrides DOTS_FOOTPRINT.
+    #[clap(long, global = true, value_name = "PATH")]
+    footprint: Option<String>,
+
+    /// When to use colors. Overrides the color set in the config file.
+    #[clap(long, global = true, value_enum, value_name = "WHEN")]
+    color: Option<ColorChoice>,
+}
+
+/// Options for pinning a dot to a specific git ref
+#[derive(Args)]
+struct PinArgs {
+    /// Pin the dot to the given branch
+    #[clap(long, conflicts_with_all = &["tag", "rev"])]
+    branch: Option<String>,
+
+    /// Pin the dot to the given tag
+    #[clap(long, conflicts_with = "rev")]
+    tag: Option<String>,
+
+    /// Pin the dot to the given commit
+    #[clap(long)]
+    rev: Option<String>,
+}
+
+impl PinArgs {
+    fn pin(&self) -> Option<Pin> {
+        match self {
+            PinArgs {
+                branch: Some(name), ..
+            } => Some(Pin::Branch(name.clone())),
+            PinArgs {
+                tag: Some(name), ..
+            } => Some(Pin::Tag(name.clone())),
+            PinArgs { rev: Some(rev), .. } => Some(Pin::Rev(rev.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Options for adding a dot from a local directory instead of cloning it
+#[derive(Args)]
+struct LocalArgs {
+    /// Use the local directory in place. The default for directories that aren't git checkouts.
+    #[clap(long, conflicts_with_all = &["copy", "branch", "tag", "rev"])]
+    link: bool,
+
+    /// Copy the local directory into the dots root without using git
+    #[clap(long, conflicts_with_all = &["branch", "tag", "rev"])]
+    copy: bool,
+}
+
+impl LocalArgs {
+    fn local(&self) -> Option<LocalMode> {
+        match self {
+            LocalArgs { link: true, .. } => Some(LocalMode::Link),
+            LocalArgs { copy: true, .. } => Some(LocalMode::Copy),
+            _ => None,
+        }
+    }
+}
+
+/// Options for choosing which profile's links to install
+#[derive(Args)]
+struct ProfileArgs {
+    /// Only link the files enabled by the given profile. Remembered for future runs.
+    #[clap(long, conflicts_with = "no-profile")]
+    profile: Option<String>,
+
+    /// Forget the remembered profile and link every file
+    #[clap(long)]
+    no_profile: bool,
+}
+
+impl ProfileArgs {
+    /** The profile to switch to, or `None` to keep using the remembered one */
+    fn profile(&self) -> Option<Option<String>> {
+        match self {
+            ProfileArgs {
+                profile: Some(name),
+                ..
+            } => Some(Some(name.clone())),
+            ProfileArgs {
+                no_profile: true, ..
+            } => Some(None),
+            _ => None,
+        }
+    }
+}
+
+/// Options for replacing pre-existing files, which override `force` in the config file
+#[derive(Args)]
+struct ForceArgs {
+    /// Will back up and replace pre-existing files when creating symlinks
+    #[clap(short, long, overrides_with = "no-force")]
+    force: bool,
+
+    /// Leave pre-existing files alone, even if force is set in the config file
+    #[clap(long, overrides_with = "force")]
+    no_force: bool,
+}
+
+impl ForceArgs {
+    /** Whether to replace pre-existing files, or `None` to use the config file */
+    fn force(&self) -> Option<bool> {
+        match self {
+            ForceArgs { force: true, .. } => Some(true),
+            ForceArgs { no_force: true, .. } => Some(false),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Downloads the given git repo as a dot
+    /// Downloads the given git repo as a dot, or adds a dot from a local directory
     Add {
-        /// A git url that points to a Dot repo containing all your dotfiles
+        /// A git url or local directory that points to a Dot containing all your dotfiles
         repo: String,
         /// Will remove pre-existing packages of the same name
         #[clap(long)]
         overwrite: bool,
+
+        #[clap(flatten)]
+        pin: PinArgs,
+
+        #[clap(flatten)]
+        local: LocalArgs,
     },
 
     /// Downloads and links dots
     Install {
-        /// An optional git url that points to a Dot repo that you want to add before installing
-        repo: Option<String>,
+        /// Only link the given dots. Anything that isn't the name of an installed dot is added as a
+        /// git url or local directory before installing. Links every dot if omitted.
+        #[clap(value_name = "DOTS")]
+        dots: Vec<String>,
 
         /// Will remove pre-existing dots of the same name
         #[clap(long)]
         overwrite: bool,
 
-        /// Will remove pre-existing directories when creating symlinks
-        #[clap(short, long)]
-        force: bool,
+        #[clap(flatten)]
+        pin: PinArgs,
+
+        #[clap(flatten)]
+        force: ForceArgs,
+
+        /// Run through the install plan without actually making any changes
+        #[clap(long)]
+        dry: bool,
+
+        /// Show how files that are in the way differ from the dotfiles that would replace them
+        #[clap(long)]
+        diff: bool,
+
+        /// Check out the exact commits recorded in dots.lock before installing
+        #[clap(long, conflicts_with = "dots")]
+        locked: bool,
+
+        #[clap(flatten)]
+        profile: ProfileArgs,
+
+        /// Save the validated install plan to the given file so it can be reviewed and applied later
+        #[clap(long, requires = "dry", value_name = "PATH")]
+        save_plan: Option<String>,
+    },
+
+    /// Links everything in an install plan saved with `install --dry --save-plan`
+    Apply {
+        /// The saved install plan. Refuses to run if any destination changed since it was saved.
+        plan: String,
+    },
+
+    /// Pulls the latest commits for installed dots and re-links them
+    Update {
+        /// The name of the dot you'd like to update. Updates all dots if omitted.
+        dot_name: Option<String>,
+
+        #[clap(flatten)]
+        force: ForceArgs,
+    },
+
+    /// Removes and recreates the links of a single dot, leaving every other dot alone
+    Relink {
+        /// The name of the dot you'd like to re-link
+        dot_name: String,
+
+        #[clap(flatten)]
+        force: ForceArgs,
 
         /// Run through the install plan without actually making any changes
         #[clap(long)]
         dry: bool,
+
+        /// Show how files that are in the way differ from the dotfiles that would replace them
+        #[clap(long)]
+        diff: bool,
     },
 
+    /// Records the current commit of every installed dot in dots.lock
+    Lock,
+
     /// Removes and unlinks dots
     Uninstall {
         /// The name of the dot you'd like to remove
@@ -86,20 +261,89 @@ enum Commands {
         /// List the git origin of each dot
         #[clap(long)]
         origins: bool,
+
+        /// List the links that each dot owns
+        #[clap(long)]
+        links: bool,
     },
 
     /// Get the current git status of each dot
     Status,
 
-    /// Returns the installed location of a given dot
+    /// Restores a file that was backed up when it was replaced by a symlink
+    Restore {
+        /// The original location of the backed up file. Lists all backups if omitted.
+        path: Option<String>,
+    },
+
+    /// Copies changes made to a copied file back into the dot it came from
+    Pull {
+        /// The location of the copied file
+        path: String,
+    },
+
+    /// Moves an existing file or directory into a dot and links it back in its place
+    Adopt {
+        /// The file or directory to adopt
+        path: String,
+
+        /// The name of the dot to move it into
+        #[clap(long, value_name = "DOT")]
+        into: String,
+
+        /// Where to put it in the dot. Defaults to its name without a leading "."
+        #[clap(long = "as", value_name = "PATH")]
+        as_path: Option<String>,
+    },
+
+    /// Finds the dot that links the given path and checks that the link is healthy
+    Which {
+        /// A linked path, or a path inside of a linked directory
+        path: String,
+    },
+
+    /// Checks the installed dots and the footprint for problems
+    Doctor {
+        /// Repairs the problems that can be repaired safely
+        #[clap(long)]
+        fix: bool,
+    },
+
+    /// Gets and sets defaults in the config file
+    Config {
+        #[clap(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Returns the installed location of a given dot, or the root that dots are installed to
     Path {
         /// The dot package name that you would like to search for
-        dot: String,
+        dot: Option<String>,
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Prints the value of a key, such as install.force
+    Get {
+        /// A dotted key, such as install.profile or variables.email
+        key: String,
+    },
+
+    /// Sets the value of a key, such as install.force
+    Set {
+        /// A dotted key, such as install.profile or variables.email
+        key: String,
+        value: String,
+    },
+
+    /// Lists every key that has been set along with its value
+    List,
+}
+
 fn main() {
-    Style::detect_color_support();
+    let cli = Cli::parse();
+
     let mut builder = Builder::new();
 
     let log_format = |buf: &mut Formatter, record: &log::Record| -> Result<(), std::io::Error> {
@@ -133,22 +377,98 @@ fn main() {
         .filter(None, log::LevelFilter::Info)
         .init();
 
-    let cli = Cli::parse();
+    let config = Config::read(Config::path());
+    let color = config.as_ref().ok().and_then(|config| config.color);
+    match cli.color.or(color).unwrap_or(ColorChoice::Auto) {
+        ColorChoice::Auto => Style::detect_color_support(),
+        ColorChoice::Always => Style::set_color_support(true),
+        ColorChoice::Never => Style::set_color_support(false),
+    }
+    let config = match config {
+        Ok(config) => config,
+        /* `dots config` has to keep working so that a broken config can be fixed */
+        Err(_) if matches!(cli.commands, Some(Commands::Config { .. })) => Config::default(),
+        Err(err) => {
+            error!("{err}");
+            process::exit(1)
+        }
+    };
 
-    match &cli.commands {
-        Some(Commands::Add { repo, overwrite }) => commands::add(repo, *overwrite),
-        Some(Commands::Install {
+    let mut env = Environment::new(config, cli.root.as_deref(), cli.footprint.as_deref());
+    let result = match &cli.commands {
+        Some(Commands::Add {
             repo,
             overwrite,
+            pin,
+            local,
+        }) => commands::add(&env, repo, *overwrite, pin.pin(), local.local()),
+        Some(Commands::Install {
+            dots,
+            overwrite,
+            pin,
+            force,
+            dry,
+            diff,
+            locked,
+            profile,
+            save_plan,
+        }) => commands::install(
+            &mut env,
+            dots,
+            *overwrite,
+            pin.pin(),
+            *locked,
+            profile.profile(),
+            force.force(),
+            *dry,
+            *diff,
+            save_plan,
+            cli.format,
+        ),
+        Some(Commands::Apply { plan }) => commands::apply(&env, plan),
+        Some(Commands::Update { dot_name, force }) => {
+            commands::update(&env, dot_name, force.force(), cli.format)
+        }
+        Some(Commands::Relink {
+            dot_name,
             force,
             dry,
-        }) => commands::install(repo, *overwrite, *force, *dry),
-        Some(Commands::Uninstall { dot_name }) => commands::uninstall(dot_name),
-        Some(Commands::List { origins }) => commands::list(*origins),
-        Some(Commands::Status) => commands::status(),
-        Some(Commands::Path { dot }) => commands::path(dot),
+            diff,
+        }) => commands::relink(&env, dot_name, force.force(), *dry, *diff, cli.format),
+        Some(Commands::Lock) => commands::lock(&env),
+        Some(Commands::Uninstall { dot_name }) => commands::uninstall(&env, dot_name),
+        Some(Commands::List { origins, links }) => {
+            commands::list(&env, *origins, *links, cli.format)
+        }
+        Some(Commands::Status) => commands::status(&env, cli.format),
+        Some(Commands::Restore { path }) => commands::restore(&env, path),
+        Some(Commands::Pull { path }) => commands::pull(&env, path),
+        Some(Commands::Adopt {
+            path,
+            into,
+            as_path,
+        }) => commands::adopt(&env, path, into, as_path),
+        Some(Commands::Which { path }) => commands::which(&env, path, cli.format),
+        Some(Commands::Doctor { fix }) => commands::doctor(&env, *fix, cli.format),
+        Some(Commands::Config { command }) => match command {
+            ConfigCommands::Get { key } => commands::config_get(key),
+            ConfigCommands::Set { key, value } => commands::config_set(key, value),
+            ConfigCommands::List => commands::config_list(),
+        },
+        Some(Commands::Path { dot }) => commands::path(&env, dot, cli.format),
         _ => {
-            println!("USAGE:\n    dots [SUBCOMMAND]")
+            println!("USAGE:\n    dots [SUBCOMMAND]");
+            Ok(())
+        }
+    };
+
+    /* Everything worth saying about an unsuccessful command has already been printed */
+    match result {
+        Ok(()) => {}
+        Err(DotsError::Unsuccessful) => process::exit(1),
+        Err(err) => {
+            error!("{err}");
+            process::exit(1)
         }
     }
 }
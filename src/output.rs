@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::fmt;
+
+/// The version of every JSON document that dots outputs. Bump this whenever a field is removed,
+/// renamed or changes type. Adding new fields is not a breaking change.
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// How commands should format what they print to stdout
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human readable text
+    Text,
+    /// Machine readable JSON
+    Json,
+}
+
+/// When to use colors in the output
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Use colors if the terminal supports them
+    Auto,
+    Always,
+    Never,
+}
+
+/*===========*\
+*  Documents  *
+\*===========*/
+
+/// A JSON document printed by a command, which always starts with the schema version
+#[derive(Serialize)]
+pub struct Document<T> {
+    version: i64,
+    #[serde(flatten)]
+    body: T,
+}
+
+impl<T: Serialize> Document<T> {
+    pub fn new(body: T) -> Self {
+        Document {
+            version: SCHEMA_VERSION,
+            body,
+        }
+    }
+}
+
+impl<T: Serialize> fmt::Display for Document<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        write!(f, "{json}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod document {
+        use crate::output::Document;
+
+        #[derive(Serialize)]
+        struct Body {
+            name: &'static str,
+            authors: Vec<&'static str>,
+            origin: Option<String>,
+            links: Vec<String>,
+        }
+
+        #[test]
+        fn it_should_start_with_the_schema_version() {
+            let document = Document::new(Body {
+                name: "a \"quote\"\\\n",
+                authors: vec!["Michael Mullins"],
+                origin: None,
+                links: vec![],
+            });
+            assert_eq!(
+                document.to_string(),
+                r#"{
+  "version": 1,
+  "name": "a \"quote\"\\\n",
+  "authors": [
+    "Michael Mullins"
+  ],
+  "origin": null,
+  "links": []
+}"#
+            );
+        }
+    }
+}
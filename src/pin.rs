@@ -0,0 +1,192 @@
+use anyhow::Result;
+use camino::Utf8Path;
+use std::collections::BTreeMap;
+use std::{fmt, fs};
+use utils::git::{self, GitError};
+
+use crate::dots::Environment;
+
+/*======*\
+*  Pins  *
+\*======*/
+
+/// A git ref that a dot's checkout should be held at
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(into = "PinTable", try_from = "PinTable")]
+pub enum Pin {
+    Branch(String),
+    Tag(String),
+    Rev(String),
+}
+
+/// How a pin is described in JSON output
+#[derive(Serialize)]
+pub struct PinOutput<'a> {
+    kind: &'static str,
+    #[serde(rename = "ref")]
+    name: &'a str,
+}
+
+/// How a pin is written to the pins file (e.g. `tag = "v1.0.0"`)
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct PinTable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    branch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rev: Option<String>,
+}
+
+impl From<Pin> for PinTable {
+    fn from(pin: Pin) -> Self {
+        match pin {
+            Pin::Branch(name) => PinTable {
+                branch: Some(name),
+                ..PinTable::default()
+            },
+            Pin::Tag(name) => PinTable {
+                tag: Some(name),
+                ..PinTable::default()
+            },
+            Pin::Rev(rev) => PinTable {
+                rev: Some(rev),
+                ..PinTable::default()
+            },
+        }
+    }
+}
+
+impl TryFrom<PinTable> for Pin {
+    type Error = String;
+
+    fn try_from(table: PinTable) -> Result<Self, Self::Error> {
+        match table {
+            PinTable {
+                branch: Some(name),
+                tag: None,
+                rev: None,
+            } => Ok(Pin::Branch(name)),
+            PinTable {
+                branch: None,
+                tag: Some(name),
+                rev: None,
+            } => Ok(Pin::Tag(name)),
+            PinTable {
+                branch: None,
+                tag: None,
+                rev: Some(rev),
+            } => Ok(Pin::Rev(rev)),
+            _ => Err(String::from(
+                "expected exactly one of `branch`, `tag` or `rev`",
+            )),
+        }
+    }
+}
+
+impl Pin {
+    /** Clones the given repo with this pin checked out */
+    pub fn clone_repo<P>(&self, url: &str, dest: P) -> Result<(), GitError>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let dest = dest.as_ref();
+        match self {
+            Pin::Branch(name) | Pin::Tag(name) => git::clone_ref(url, dest, name),
+            Pin::Rev(rev) => {
+                git::clone_full(url, dest)?;
+                git::checkout(dest, rev)
+            }
+        }
+    }
+
+    pub fn output(&self) -> PinOutput<'_> {
+        let (kind, name) = match self {
+            Pin::Branch(name) => ("branch", name),
+            Pin::Tag(name) => ("tag", name),
+            Pin::Rev(rev) => ("rev", rev),
+        };
+        PinOutput { kind, name }
+    }
+
+    /** Whether new commits should be pulled in when updating */
+    pub fn is_movable(&self) -> bool {
+        matches!(self, Pin::Branch(_))
+    }
+
+    /**
+     * Whether the checkout at the given path no longer matches this pin. A checkout held at a
+     * commit from the branch's history, as `install --locked` does, hasn't drifted.
+     */
+    pub fn is_drifted<P>(&self, dir: P) -> Result<bool, GitError>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let dir = dir.as_ref();
+        match self {
+            Pin::Branch(name) => match git::get_branch(dir)?.as_str() {
+                "HEAD" => Ok(!git::is_ancestor(dir, "HEAD", name)?),
+                branch => Ok(branch != name),
+            },
+            Pin::Tag(rev) | Pin::Rev(rev) => {
+                Ok(git::rev_parse(dir, "HEAD")? != git::rev_parse(dir, rev)?)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pin::Branch(name) => write!(f, "branch {name}"),
+            Pin::Tag(name) => write!(f, "tag {name}"),
+            Pin::Rev(rev) => write!(f, "rev {rev}"),
+        }
+    }
+}
+
+/// The pins for every installed dot, keyed by dot name
+#[derive(Serialize, Deserialize, Default)]
+pub struct Pins {
+    #[serde(flatten)]
+    pins: BTreeMap<String, Pin>,
+}
+
+impl Pins {
+    pub fn read(env: &Environment) -> Pins {
+        let path = env.pins_path();
+        let Ok(string) = fs::read_to_string(&path) else {
+            return Pins::default();
+        };
+        toml::from_str(&string).unwrap_or_else(|err| {
+            warn!("Error parsing {path}:\n{err}");
+            Pins::default()
+        })
+    }
+
+    /** Writes the pins to the pins file, removing the file if there are no pins */
+    pub fn save(&self, env: &Environment) -> Result<()> {
+        let path = env.pins_path();
+        if self.pins.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        let contents = toml::to_string(self)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn get(&self, dot_name: &str) -> Option<&Pin> {
+        self.pins.get(dot_name)
+    }
+
+    pub fn set(&mut self, dot_name: &str, pin: Option<Pin>) {
+        match pin {
+            Some(pin) => self.pins.insert(dot_name.to_string(), pin),
+            None => self.pins.remove(dot_name),
+        };
+    }
+}
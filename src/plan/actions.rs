@@ -0,0 +1,254 @@
+use anyhow::{anyhow, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::fs;
+use utils::fs::{hash, hash_file};
+use walkdir::WalkDir;
+
+use crate::output::{Document, SCHEMA_VERSION};
+use crate::template::RenderedTemplate;
+
+use super::links::Link;
+
+/*=========*\
+*  Actions  *
+\*=========*/
+
+/// What needs to happen to a destination before its link can be created
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Nothing is at the destination yet
+    Create,
+    /// A pre-existing symlink will be removed
+    ReplaceSymlink,
+    /// A copy of the dotfile that hasn't been changed will be removed
+    ReplaceCopy,
+    /// A hard link to the dotfile will be removed
+    ReplaceHardlink,
+    /// A pre-existing file or directory will be moved into the backups directory
+    Backup,
+    /// A pre-existing file or directory will be moved into the backups directory, as confirmed by
+    /// the user
+    ConfirmedBackup,
+    /// A pre-existing file or directory will be removed, as confirmed by the user
+    Overwrite,
+    /// The user chose to leave the destination alone, so the link won't be created
+    Skip,
+}
+
+/*==============*\
+*  Fingerprints  *
+\*==============*/
+
+/// A summary of what was at a destination, used to tell whether it changed since it was planned
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Fingerprint {
+    Missing,
+    Symlink(Utf8PathBuf),
+    File(String),
+    /// A hash of every path in the directory along with what's at each one
+    Directory(String),
+}
+
+impl Fingerprint {
+    /** Takes a fingerprint of whatever is currently at the given path */
+    pub fn of(path: &Utf8Path) -> Result<Fingerprint> {
+        let Ok(metadata) = path.symlink_metadata() else {
+            return Ok(Fingerprint::Missing);
+        };
+
+        if metadata.is_symlink() {
+            let target = Utf8PathBuf::try_from(fs::read_link(path)?)?;
+            Ok(Fingerprint::Symlink(target))
+        } else if metadata.is_dir() {
+            Ok(Fingerprint::Directory(hash_dir(path)?))
+        } else {
+            Ok(Fingerprint::File(hash_file(path)?))
+        }
+    }
+}
+
+/**
+ * Hashes a directory tree the same way copies are hashed. Each entry adds its path relative to the
+ * directory, followed by the hash of a file or the target of a symlink.
+ */
+fn hash_dir(dir: &Utf8Path) -> Result<String> {
+    let mut summary = String::new();
+    for entry in WalkDir::new(dir).min_depth(1).sort_by_file_name() {
+        let entry = entry?;
+        let path = Utf8Path::from_path(entry.path())
+            .ok_or_else(|| anyhow!("{} isn't valid UTF-8", entry.path().display()))?;
+        let relative = path.strip_prefix(dir)?;
+        let contents = if entry.file_type().is_symlink() {
+            format!("-> {}", fs::read_link(path)?.display())
+        } else if entry.file_type().is_dir() {
+            String::from("/")
+        } else {
+            hash_file(path)?
+        };
+        summary.push_str(&format!("{relative} {contents}\n"));
+    }
+    Ok(hash(summary.as_bytes()))
+}
+
+/*===============*\
+*  Planned Links  *
+\*===============*/
+
+/// A link along with everything needed to create it exactly as it was planned
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct PlannedLink {
+    pub dot: String,
+    /// The destination exactly as it's written in the dot's Dot.toml
+    pub key: String,
+    #[serde(flatten)]
+    pub link: Link,
+    pub template: Option<RenderedTemplate>,
+    pub action: Action,
+    /// The state of the destination when the plan was made
+    pub fingerprint: Fingerprint,
+}
+
+impl PlannedLink {
+    /** Whether the destination is no longer in the state it was in when the plan was made */
+    pub fn has_changed(&self) -> bool {
+        match Fingerprint::of(&self.link.dest.path) {
+            Ok(fingerprint) => fingerprint != self.fingerprint,
+            Err(_) => true,
+        }
+    }
+}
+
+/*============*\
+*  Plan Files  *
+\*============*/
+
+/// A validated plan that was saved with `install --save-plan` to be applied later
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct PlanFile {
+    /// Whether the plan was made with `--force`, which is required for backups
+    #[serde(default)]
+    pub force: bool,
+    /// The names of the dots the plan was limited to. Every dot was planned when this is `None`.
+    #[serde(default)]
+    pub only: Option<Vec<String>>,
+    pub links: Vec<PlannedLink>,
+}
+
+/// Just enough of a plan file to tell which version of the format it was saved with
+#[derive(Deserialize)]
+struct PlanVersion {
+    version: Option<i64>,
+}
+
+impl PlanFile {
+    pub fn read<P>(path: P) -> Result<PlanFile>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let path = path.as_ref();
+        let string = fs::read_to_string(path)?;
+        let parse_error = |err| anyhow!("Unable to parse {path}: {err}");
+
+        let PlanVersion { version } = serde_json::from_str(&string).map_err(parse_error)?;
+        match version {
+            Some(SCHEMA_VERSION) => {}
+            Some(version) => {
+                return Err(anyhow!(
+                    "{path} was saved with version {version} of the plan format, expected version {SCHEMA_VERSION}"
+                ))
+            }
+            None => return Err(anyhow!("{path} is missing a version")),
+        }
+
+        serde_json::from_str(&string).map_err(parse_error)
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        fs::write(path.as_ref(), format!("{}\n", Document::new(self)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod plan_file {
+        use crate::plan::actions::{Action, Fingerprint, PlanFile, PlannedLink};
+        use crate::plan::links::{Link, LinkMode};
+        use crate::template::RenderedTemplate;
+        use camino::Utf8Path;
+        use tempfile::tempdir;
+
+        #[test]
+        fn it_should_read_what_it_saves() {
+            let dir = tempdir().unwrap();
+            let path = Utf8Path::from_path(dir.path()).unwrap().join("plan.json");
+            let plan = PlanFile {
+                force: true,
+                only: Some(vec![String::from("example_dot")]),
+                links: vec![PlannedLink {
+                    dot: String::from("example_dot"),
+                    key: String::from("~/.gitconfig"),
+                    link: Link::new("/dots/git/gitconfig", "/home/.gitconfig")
+                        .with_mode(LinkMode::Copy),
+                    template: Some(RenderedTemplate {
+                        output: "/dots/.generated/gitconfig".into(),
+                        contents: String::from("[user]\n\tname = \"Michael\"\n"),
+                    }),
+                    action: Action::Backup,
+                    fingerprint: Fingerprint::File(String::from("cbf29ce484222325")),
+                }],
+            };
+
+            plan.save(&path).unwrap();
+            let saved = PlanFile::read(&path).unwrap();
+
+            assert!(saved.force);
+            assert_eq!(saved.only, plan.only);
+            assert_eq!(saved.links.len(), 1);
+            let link = &saved.links[0];
+            assert_eq!(link.key, "~/.gitconfig");
+            assert_eq!(link.link, plan.links[0].link);
+            assert_eq!(link.template, plan.links[0].template);
+            assert_eq!(link.action, Action::Backup);
+            assert_eq!(link.fingerprint, plan.links[0].fingerprint);
+        }
+
+        #[test]
+        fn it_should_take_a_fingerprint_of_the_destination() {
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            let path = root.join("file");
+
+            assert_eq!(Fingerprint::of(&path).unwrap(), Fingerprint::Missing);
+            std::fs::write(&path, "").unwrap();
+            assert!(matches!(
+                Fingerprint::of(&path).unwrap(),
+                Fingerprint::File(_)
+            ));
+            assert!(matches!(
+                Fingerprint::of(root).unwrap(),
+                Fingerprint::Directory(_)
+            ));
+        }
+
+        #[test]
+        fn it_should_change_the_fingerprint_of_a_directory_when_its_contents_change() {
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            std::fs::create_dir(root.join("nested")).unwrap();
+            std::fs::write(root.join("nested/file"), "original").unwrap();
+            let original = Fingerprint::of(root).unwrap();
+
+            assert_eq!(Fingerprint::of(root).unwrap(), original);
+            std::fs::write(root.join("nested/file"), "changed").unwrap();
+            assert_ne!(Fingerprint::of(root).unwrap(), original);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use super::diff::describe;
+use super::resolve::ResolvedLink;
+
+/*===========*\
+*  Decisions  *
+\*===========*/
+
+/// How the user chose to handle a destination that already exists
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// Remove what's there and link the dotfile in its place
+    Overwrite,
+    /// Move what's there into the backups directory and link the dotfile in its place
+    Backup,
+    /// Leave what's there alone and don't link this dotfile
+    Skip,
+    /// Stop the install without changing anything
+    Abort,
+}
+
+impl Decision {
+    fn parse(answer: &str) -> Option<Decision> {
+        match answer.trim().to_lowercase().as_str() {
+            "o" | "overwrite" => Some(Decision::Overwrite),
+            "b" | "backup" | "back up" => Some(Decision::Backup),
+            "s" | "skip" => Some(Decision::Skip),
+            "a" | "abort" => Some(Decision::Abort),
+            _ => None,
+        }
+    }
+}
+
+/*=========*\
+*  Prompts  *
+\*=========*/
+
+/// Asks the user what to do with destinations that already exist
+pub struct Prompt {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl Prompt {
+    pub fn new(input: impl BufRead + 'static, output: impl Write + 'static) -> Prompt {
+        Prompt {
+            input: Box::new(input),
+            output: Box::new(output),
+        }
+    }
+
+    /** Prompts on stderr so that stdout can still be piped */
+    pub fn stdin() -> Prompt {
+        Prompt::new(io::stdin().lock(), io::stderr())
+    }
+
+    /** Whether there is someone at a terminal to answer prompts */
+    pub fn is_available() -> bool {
+        io::stdin().is_terminal() && io::stderr().is_terminal()
+    }
+
+    /**
+     * Describes what's at the destination of the given link and asks what to do with it until a
+     * valid answer is given. Running out of input aborts.
+     */
+    pub fn ask(&mut self, link: &ResolvedLink) -> io::Result<Decision> {
+        writeln!(self.output, "\n{}", describe(link))?;
+        loop {
+            write!(
+                self.output,
+                "[o]verwrite, [b]ack up, [s]kip this link or [a]bort? "
+            )?;
+            self.output.flush()?;
+
+            let mut answer = String::new();
+            if self.input.read_line(&mut answer)? == 0 {
+                writeln!(self.output)?;
+                return Ok(Decision::Abort);
+            }
+            if let Some(decision) = Decision::parse(&answer) {
+                return Ok(decision);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod prompt {
+        use crate::plan::confirm::{Decision, Prompt};
+        use crate::plan::links::Link;
+        use crate::plan::resolve::resolve;
+        use camino::Utf8Path;
+        use std::fs;
+        use std::io::Cursor;
+        use tempfile::tempdir;
+
+        #[test]
+        fn it_should_ask_until_it_gets_a_valid_answer() {
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            fs::write(root.join("bashrc"), "export EDITOR=vim\n").unwrap();
+            fs::write(root.join(".bashrc"), "export EDITOR=nano\n").unwrap();
+            let mut link = resolve(root, Link::new("bashrc", ".bashrc"));
+            link.dest.path = Some(root.join(".bashrc"));
+
+            let mut prompt = Prompt::new(Cursor::new("maybe\nb\n"), Vec::new());
+            assert_eq!(prompt.ask(&link).unwrap(), Decision::Backup);
+
+            let mut prompt = Prompt::new(Cursor::new("skip\n"), Vec::new());
+            assert_eq!(prompt.ask(&link).unwrap(), Decision::Skip);
+        }
+
+        #[test]
+        fn it_should_abort_if_it_runs_out_of_input() {
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            fs::write(root.join("bashrc"), "").unwrap();
+            fs::write(root.join(".bashrc"), "").unwrap();
+            let mut link = resolve(root, Link::new("bashrc", ".bashrc"));
+            link.dest.path = Some(root.join(".bashrc"));
+
+            let mut prompt = Prompt::new(Cursor::new(""), Vec::new());
+            assert_eq!(prompt.ask(&link).unwrap(), Decision::Abort);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+use std::fs;
+use walkdir::WalkDir;
+
+use super::resolve::{file_type_to_str, ResolveIssueKind, ResolvedLink};
+
+mod styles {
+    use utils::stylize::Style;
+
+    pub const TITLE: Style = Style::new().bold();
+    pub const ADDED: Style = Style::new().green();
+    pub const REMOVED: Style = Style::new().red();
+    pub const CHANGED: Style = Style::new().yellow();
+    pub const HUNK: Style = Style::new().cyan();
+}
+
+/// The most entries listed when summarizing how two directories differ
+const MAX_ENTRIES: usize = 20;
+
+/*==============*\
+*  Descriptions  *
+\*==============*/
+
+/** Whether something that isn't managed by dots is in the way of the given link */
+pub fn is_in_the_way(link: &ResolvedLink) -> bool {
+    link.dest
+        .issues
+        .iter()
+        .any(|issue| matches!(issue.kind, ResolveIssueKind::AlreadyExists(_)))
+}
+
+/**
+ * Describes what already exists at the destination of the given link: its type, its size, and how
+ * it differs from the dotfile that would replace it.
+ */
+pub fn describe(link: &ResolvedLink) -> String {
+    let dest_name = &link.dest.original.path;
+    let Some(metadata) = link
+        .dest
+        .path
+        .as_ref()
+        .and_then(|dest| dest.symlink_metadata().ok())
+    else {
+        return format!("{dest_name} already exists");
+    };
+
+    let file_type = metadata.file_type();
+    let file_type = file_type_to_str(&file_type);
+    let title = if metadata.is_file() {
+        let size = metadata.len();
+        format!("{dest_name} already exists as {file_type} ({size} bytes)")
+    } else {
+        format!("{dest_name} already exists as {file_type}")
+    };
+
+    match differences(link) {
+        Some(differences) => format!("{}\n{differences}", styles::TITLE.apply(title)),
+        None => styles::TITLE.apply(title).to_string(),
+    }
+}
+
+/**
+ * How whatever is at the destination of the given link differs from the dotfile that would replace
+ * it. Files get a unified diff and directories get a summary of every file that differs.
+ */
+pub fn differences(link: &ResolvedLink) -> Option<String> {
+    let dest = link.dest.path.as_ref()?;
+    let dest_name = link.dest.original.path.as_str();
+    let src_name = link.src.original.path.as_str();
+    let metadata = dest.symlink_metadata().ok()?;
+
+    if metadata.is_symlink() {
+        let target = dest.read_link_utf8().ok()?;
+        return Some(format!("it points to {target}"));
+    }
+
+    let source = match &link.template {
+        Some(template) => Source::File(template.contents.clone().into_bytes()),
+        None => Source::read(link.src.path.as_deref()?)?,
+    };
+
+    match source {
+        Source::File(source) if metadata.is_file() => {
+            let existing = fs::read(dest).ok()?;
+            Some(diff(&existing, &source, dest_name, src_name))
+        }
+        Source::Directory(src) if metadata.is_dir() => {
+            Some(diff_dirs(dest, &src, dest_name, src_name))
+        }
+        _ if metadata.is_dir() => Some(summarize_dir(dest)),
+        _ => None,
+    }
+}
+
+/// The dotfile that would replace a destination
+enum Source {
+    File(Vec<u8>),
+    Directory(Utf8PathBuf),
+}
+
+impl Source {
+    fn read(path: &Utf8Path) -> Option<Source> {
+        if path.is_dir() {
+            Some(Source::Directory(path.to_path_buf()))
+        } else {
+            fs::read(path).ok().map(Source::File)
+        }
+    }
+}
+
+/*=======*\
+*  Diffs  *
+\*=======*/
+
+/** Files that aren't valid UTF-8 or contain a null byte are treated as binary */
+fn as_text(bytes: &[u8]) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(bytes).ok()
+}
+
+/** A unified diff from the existing file to the dotfile that would replace it */
+fn diff(existing: &[u8], source: &[u8], existing_name: &str, source_name: &str) -> String {
+    if existing == source {
+        return format!("it's identical to {source_name}");
+    }
+    let (Some(existing), Some(source)) = (as_text(existing), as_text(source)) else {
+        return format!("binary files {existing_name} and {source_name} differ");
+    };
+
+    let diff = TextDiff::from_lines(existing, source);
+    let mut lines = vec![format!("--- {existing_name}"), format!("+++ {source_name}")];
+    for hunk in diff.unified_diff().iter_hunks() {
+        lines.push(styles::HUNK.apply(hunk.header()).to_string());
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            lines.push(match change.tag() {
+                ChangeTag::Equal => format!(" {line}"),
+                ChangeTag::Delete => styles::REMOVED.apply(format!("-{line}")).to_string(),
+                ChangeTag::Insert => styles::ADDED.apply(format!("+{line}")).to_string(),
+            });
+        }
+    }
+    lines.join("\n")
+}
+
+/** Every file under the given directory, by its path relative to the directory */
+fn files(dir: &Utf8Path) -> BTreeMap<Utf8PathBuf, Utf8PathBuf> {
+    WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|entry| !entry.file_type().is_dir())
+        .filter_map(|entry| Utf8PathBuf::from_path_buf(entry.into_path()).ok())
+        .filter_map(|path| {
+            let relative = path.strip_prefix(dir).ok()?.to_path_buf();
+            Some((relative, path))
+        })
+        .collect()
+}
+
+/**
+ * Summarizes how the existing directory differs from the directory that would replace it. Files
+ * that were removed, added or changed are listed, followed by how many are the same.
+ */
+fn diff_dirs(
+    existing: &Utf8Path,
+    source: &Utf8Path,
+    existing_name: &str,
+    source_name: &str,
+) -> String {
+    let existing_files = files(existing);
+    let source_files = files(source);
+
+    let mut entries = vec![];
+    let mut identical = 0;
+    for (relative, path) in &existing_files {
+        match source_files.get(relative) {
+            None => entries.push(styles::REMOVED.apply(format!("- {relative}")).to_string()),
+            Some(source_path) => {
+                let is_same = match (fs::read(path), fs::read(source_path)) {
+                    (Ok(existing), Ok(source)) => existing == source,
+                    _ => false,
+                };
+                if is_same {
+                    identical += 1;
+                } else {
+                    entries.push(styles::CHANGED.apply(format!("~ {relative}")).to_string());
+                }
+            }
+        }
+    }
+    for relative in source_files.keys() {
+        if !existing_files.contains_key(relative) {
+            entries.push(styles::ADDED.apply(format!("+ {relative}")).to_string());
+        }
+    }
+
+    if entries.is_empty() {
+        return format!("its files are identical to {source_name}");
+    }
+
+    let mut lines = vec![format!("--- {existing_name}"), format!("+++ {source_name}")];
+    let hidden = entries.len().saturating_sub(MAX_ENTRIES);
+    lines.extend(entries.into_iter().take(MAX_ENTRIES));
+    if hidden > 0 {
+        lines.push(format!("... and {hidden} more"));
+    }
+    match identical {
+        0 => {}
+        1 => lines.push(String::from("1 other file is identical")),
+        _ => lines.push(format!("{identical} other files are identical")),
+    }
+    lines.join("\n")
+}
+
+/** Summarizes what's in a directory that would be replaced by a file */
+fn summarize_dir(dir: &Utf8Path) -> String {
+    let files = files(dir);
+    let size: u64 = files
+        .values()
+        .filter_map(|path| path.symlink_metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum();
+    format!("it contains {} files ({size} bytes)", files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    mod describe {
+        use crate::plan::diff::describe;
+        use crate::plan::links::Link;
+        use crate::plan::resolve::resolve;
+        use camino::Utf8Path;
+        use std::fs;
+        use tempfile::tempdir;
+        use utils::stylize::Style;
+
+        #[test]
+        fn it_should_diff_existing_files_against_the_dotfile() {
+            Style::set_color_support(false);
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            fs::write(root.join("bashrc"), "alias ll='ls -l'\nexport EDITOR=vim\n").unwrap();
+            fs::write(
+                root.join(".bashrc"),
+                "alias ll='ls -l'\nexport EDITOR=nano\n",
+            )
+            .unwrap();
+            let mut link = resolve(root, Link::new("bashrc", ".bashrc"));
+            link.dest.path = Some(root.join(".bashrc"));
+
+            let description = describe(&link);
+            assert_eq!(
+                description,
+                String::from(
+                    ".bashrc already exists as a file (36 bytes)\n--- .bashrc\n+++ bashrc\n@@ -1,2 +1,2 @@\n alias ll='ls -l'\n-export EDITOR=nano\n+export EDITOR=vim"
+                )
+            );
+        }
+
+        #[test]
+        fn it_should_not_diff_binary_files() {
+            Style::set_color_support(false);
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            fs::write(root.join("image"), [0x89, 0x50, 0x00]).unwrap();
+            fs::write(root.join(".image"), [0x89, 0x00]).unwrap();
+            let mut link = resolve(root, Link::new("image", ".image"));
+            link.dest.path = Some(root.join(".image"));
+
+            let description = describe(&link);
+            assert!(description.ends_with("binary files .image and image differ"));
+        }
+
+        #[test]
+        fn it_should_summarize_how_directories_differ() {
+            Style::set_color_support(false);
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap();
+            fs::create_dir_all(root.join("nvim/lua")).unwrap();
+            fs::create_dir_all(root.join(".nvim/lua")).unwrap();
+            fs::write(root.join("nvim/init.lua"), "vim.opt.number = true\n").unwrap();
+            fs::write(root.join("nvim/lua/plugins.lua"), "return {}\n").unwrap();
+            fs::write(root.join("nvim/lua/keys.lua"), "return {}\n").unwrap();
+            fs::write(root.join(".nvim/init.vim"), "set number\n").unwrap();
+            fs::write(
+                root.join(".nvim/lua/plugins.lua"),
+                "return { 'telescope' }\n",
+            )
+            .unwrap();
+            fs::write(root.join(".nvim/lua/keys.lua"), "return {}\n").unwrap();
+            let mut link = resolve(root, Link::new("nvim", ".nvim"));
+            link.dest.path = Some(root.join(".nvim"));
+
+            let description = describe(&link);
+            assert_eq!(
+                description,
+                ".nvim already exists as a directory\n--- .nvim\n+++ nvim\n- init.vim\n~ lua/plugins.lua\n+ init.lua\n1 other file is identical"
+            );
+        }
+    }
+}
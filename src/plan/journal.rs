@@ -0,0 +1,539 @@
+use anyhow::{anyhow, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{fmt, fs, io, os::unix, os::unix::fs::MetadataExt, time::SystemTime};
+use utils::fs::{move_path, write_atomic};
+
+use crate::footprint::{FootprintBackup, LinkRecord};
+use crate::fs_manager::FSManager;
+use crate::template::RenderedTemplate;
+
+use super::links::Link;
+
+/*=======*\
+*  Steps  *
+\*=======*/
+
+/// A single change made to the filesystem by a command
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum Step {
+    /// A pre-existing symlink was removed
+    RemovedSymlink {
+        path: Utf8PathBuf,
+        target: Utf8PathBuf,
+    },
+    /// A pre-existing file or directory was moved into the backups directory
+    BackedUp { backup: FootprintBackup },
+    /// A pre-existing file or directory was moved aside to be removed once the plan succeeds
+    Removed {
+        path: Utf8PathBuf,
+        moved_to: Utf8PathBuf,
+    },
+    /// A missing parent directory was created
+    CreatedDir { path: Utf8PathBuf },
+    /// A symlink was created for the given link
+    CreatedSymlink { link: Link },
+    /// A dotfile was copied for the given link
+    CreatedCopy { link: Link },
+    /// A pre-existing hard link to a dotfile was removed
+    RemovedHardlink { link: Link },
+    /// A hard link was created for the given link
+    CreatedHardlink { link: Link },
+    /// A template was rendered. Holds the contents of the output before it was rendered.
+    RenderedTemplate {
+        path: Utf8PathBuf,
+        previous: Option<String>,
+    },
+    /// A template output that's no longer produced was removed. Holds its contents.
+    RemovedOutput { path: Utf8PathBuf, contents: String },
+    /// A file or directory was moved to a new location
+    Moved { from: Utf8PathBuf, to: Utf8PathBuf },
+    /// A file was copied to the given path, which was empty beforehand
+    CopiedFile { path: Utf8PathBuf },
+    /// A file was written. Holds its contents before it was written.
+    WroteFile {
+        path: Utf8PathBuf,
+        previous: Option<String>,
+    },
+    /// A backup was moved back to where it was taken from
+    RestoredBackup { backup: FootprintBackup },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::RemovedSymlink { path, .. } => write!(f, "removed symlink {path}"),
+            Step::BackedUp { backup } => write!(f, "backed up {}", backup.path),
+            Step::Removed { path, .. } => write!(f, "removed {path}"),
+            Step::CreatedDir { path } => write!(f, "created directory {path}"),
+            Step::CreatedSymlink { link } => write!(f, "created symlink {link:?}"),
+            Step::CreatedCopy { link } => write!(f, "created copy {link:?}"),
+            Step::RemovedHardlink { link } => write!(f, "removed hardlink {link:?}"),
+            Step::CreatedHardlink { link } => write!(f, "created hardlink {link:?}"),
+            Step::RenderedTemplate { path, .. } => write!(f, "rendered template {path}"),
+            Step::RemovedOutput { path, .. } => write!(f, "removed template output {path}"),
+            Step::Moved { from, to } => write!(f, "moved {from} to {to}"),
+            Step::CopiedFile { path } => write!(f, "copied {path}"),
+            Step::WroteFile { path, .. } => write!(f, "wrote {path}"),
+            Step::RestoredBackup { backup } => write!(f, "restored {}", backup.path),
+        }
+    }
+}
+
+/*=========*\
+*  Journal  *
+\*=========*/
+
+/**
+ * Performs and records every change made by a command so that they can be undone if the command
+ * fails part way through. Each step is written to the journal next to the footprint before the
+ * change is made, so that if dots is interrupted the next command can still undo everything with
+ * [`Journal::recover`]. Undoing a step that was recorded but never made does nothing.
+ */
+#[derive(Serialize, Deserialize)]
+pub struct Journal {
+    /// The timestamp used for every backup taken during this run
+    timestamp: String,
+    /// The contents of the footprint file before the plan started, if there was one
+    footprint: Option<String>,
+    steps: Vec<Step>,
+    #[serde(skip)]
+    path: Utf8PathBuf,
+}
+
+impl Journal {
+    /**
+     * Starts a new journal for the given [`FSManager`] and writes it to disk. Any pending footprint
+     * changes are saved first, and every change after that is held back until
+     * [`FSManager::save`] is called.
+     */
+    pub fn begin(fs_manager: &mut FSManager) -> Result<Journal> {
+        fs_manager.save()?;
+        let journal = Journal {
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            footprint: fs_manager.footprint_contents(),
+            steps: vec![],
+            path: fs_manager.journal_path().to_path_buf(),
+        };
+        if let Some(parent) = journal.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        journal.save()?;
+        fs_manager.defer_saves();
+        Ok(journal)
+    }
+
+    /**
+     * Rolls back the changes of a plan that never finished, usually because dots was interrupted.
+     * Returns whether there was anything to roll back.
+     */
+    pub fn recover(fs_manager: &mut FSManager) -> Result<bool> {
+        let path = fs_manager.journal_path().to_path_buf();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err).with_context(|| format!("Unable to read {path}")),
+        };
+        let mut journal: Journal =
+            toml::from_str(&contents).with_context(|| format!("Unable to parse {path}"))?;
+        journal.path = path.clone();
+
+        warn!("The last install didn't finish, rolling back its changes...");
+        journal.rollback(fs_manager).map_err(|err| {
+            anyhow!("{err}\nWhat's left to undo is kept in {path} for the next command to retry")
+        })?;
+        Ok(true)
+    }
+
+    fn save(&self) -> Result<()> {
+        let contents = toml::to_string(self)?;
+        write_atomic(&self.path, contents).with_context(|| format!("Unable to write {}", self.path))
+    }
+
+    /** Records a step that is about to be performed */
+    fn record(&mut self, step: Step) -> Result<()> {
+        self.steps.push(step);
+        self.save()
+    }
+
+    /** Removes the symlink at the given path */
+    pub fn remove_symlink(&mut self, path: &Utf8Path) -> Result<()> {
+        let target = Utf8PathBuf::try_from(fs::read_link(path)?)?;
+        self.record(Step::RemovedSymlink {
+            path: path.to_path_buf(),
+            target,
+        })?;
+        fs::remove_file(path).with_context(|| format!("Unable to remove {path}"))
+    }
+
+    /** Moves the file or directory at the given path into the backups directory */
+    pub fn backup_path(&mut self, fs_manager: &mut FSManager, path: &Utf8Path) -> Result<()> {
+        let backup = fs_manager
+            .backup_for(path, &self.timestamp)
+            .with_context(|| format!("Unable to back up {path}"))?;
+        self.record(Step::BackedUp {
+            backup: backup.clone(),
+        })?;
+        fs_manager
+            .create_backup(&backup)
+            .with_context(|| format!("Unable to back up {path}"))?;
+        info!("Backed up {path}. Run `dots restore` to put it back.");
+        fs_manager.track_backup(&backup)
+    }
+
+    /**
+     * Removes the file or directory at the given path. It's only moved aside until the journal is
+     * committed, so that it can still be put back if the plan fails.
+     */
+    pub fn remove_path(&mut self, path: &Utf8Path) -> Result<()> {
+        let name = path.file_name().unwrap_or_default();
+        let moved_to = path.with_file_name(format!(".{name}.{}.dots-removed", self.timestamp));
+        self.record(Step::Removed {
+            path: path.to_path_buf(),
+            moved_to: moved_to.clone(),
+        })?;
+        fs::rename(path, &moved_to).with_context(|| format!("Unable to remove {path}"))
+    }
+
+    /**
+     * Removes a template output. Its contents are kept in the journal rather than moving it aside,
+     * so that the directories it leaves empty can be removed too.
+     */
+    pub fn remove_output(&mut self, path: &Utf8Path) -> Result<()> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("Unable to read {path}"))?;
+        self.record(Step::RemovedOutput {
+            path: path.to_path_buf(),
+            contents,
+        })?;
+        fs::remove_file(path).with_context(|| format!("Unable to remove {path}"))
+    }
+
+    /** Moves the file or directory at the given path to a new location, which has to be empty */
+    pub fn move_path(&mut self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+        self.record(Step::Moved {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        })?;
+        move_path(from, to).with_context(|| format!("Unable to move {from} to {to}"))
+    }
+
+    /** Copies a file over the given path. Anything already there is removed first. */
+    pub fn copy_file(&mut self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+        if to.symlink_metadata().is_ok() {
+            self.remove_path(to)?;
+        }
+        self.record(Step::CopiedFile {
+            path: to.to_path_buf(),
+        })?;
+        fs::copy(from, to).with_context(|| format!("Unable to copy {from} to {to}"))?;
+        Ok(())
+    }
+
+    /** Writes the given contents to a file, replacing whatever it contained */
+    pub fn write_file(&mut self, path: &Utf8Path, contents: &str) -> Result<()> {
+        let previous = fs::read_to_string(path).ok();
+        self.record(Step::WroteFile {
+            path: path.to_path_buf(),
+            previous,
+        })?;
+        write_atomic(path, contents).with_context(|| format!("Unable to write {path}"))
+    }
+
+    /** Moves a backup back to where it was taken from */
+    pub fn restore_backup(
+        &mut self,
+        fs_manager: &FSManager,
+        backup: &FootprintBackup,
+    ) -> Result<()> {
+        self.record(Step::RestoredBackup {
+            backup: backup.clone(),
+        })?;
+        fs_manager.restore_backup(backup)
+    }
+
+    /** Creates the given directory and any of its missing parents */
+    pub fn create_dir_all(&mut self, path: &Utf8Path) -> Result<()> {
+        let mut missing: Vec<&Utf8Path> = path
+            .ancestors()
+            .take_while(|ancestor| !ancestor.exists())
+            .collect();
+        missing.reverse();
+
+        for dir in missing {
+            self.record(Step::CreatedDir {
+                path: dir.to_path_buf(),
+            })?;
+            fs::create_dir(dir).with_context(|| format!("Unable to create directory {dir}"))?;
+        }
+        Ok(())
+    }
+
+    /** Creates the symlink for the given link and tracks it in the footprint */
+    pub fn create_symlink(
+        &mut self,
+        fs_manager: &mut FSManager,
+        link: &Link,
+        record: LinkRecord,
+    ) -> Result<()> {
+        self.record(Step::CreatedSymlink { link: link.clone() })?;
+        fs_manager.create_symlink(link)?;
+        fs_manager.track_link(link, record)
+    }
+
+    /** Copies the source of the given link to its destination and tracks it in the footprint */
+    pub fn create_copy(
+        &mut self,
+        fs_manager: &mut FSManager,
+        link: &Link,
+        record: LinkRecord,
+    ) -> Result<()> {
+        self.record(Step::CreatedCopy { link: link.clone() })?;
+        fs_manager.create_copy(link)?;
+        fs_manager.track_link(link, record)
+    }
+
+    /** Removes an existing hard link between the given link's destination and its source */
+    pub fn remove_hardlink(&mut self, fs_manager: &FSManager, link: &Link) -> Result<()> {
+        self.record(Step::RemovedHardlink { link: link.clone() })?;
+        fs_manager.remove_hardlink(link)?;
+        Ok(())
+    }
+
+    /** Creates the hard link for the given link and tracks it in the footprint */
+    pub fn create_hardlink(
+        &mut self,
+        fs_manager: &mut FSManager,
+        link: &Link,
+        record: LinkRecord,
+    ) -> Result<()> {
+        self.record(Step::CreatedHardlink { link: link.clone() })?;
+        fs_manager.create_hardlink(link)?;
+        fs_manager.track_link(link, record)
+    }
+
+    /** Writes the rendered template to its output path and tracks it in the footprint */
+    pub fn render_template(
+        &mut self,
+        fs_manager: &mut FSManager,
+        template: &RenderedTemplate,
+    ) -> Result<()> {
+        let path = template.output.clone();
+        let previous = fs::read_to_string(&path).ok();
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent)?;
+        }
+        self.record(Step::RenderedTemplate {
+            path: path.clone(),
+            previous,
+        })?;
+        fs_manager
+            .write_generated(template)
+            .with_context(|| format!("Unable to write {path}"))?;
+        fs_manager.track_generated(&path)
+    }
+
+    /**
+     * Keeps all changes, deleting anything that was removed for good. The footprint needs to be
+     * saved first, since the changes can't be rolled back once the journal is gone.
+     */
+    pub fn commit(self) -> Result<()> {
+        debug!("committing {} install steps", self.steps.len());
+        fs::remove_file(&self.path).with_context(|| format!("Unable to remove {}", self.path))?;
+        for step in &self.steps {
+            let Step::Removed { moved_to, .. } = step else {
+                continue;
+            };
+            let result = match moved_to.symlink_metadata() {
+                Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(moved_to),
+                _ => fs::remove_file(moved_to),
+            };
+            if let Err(err) = result {
+                warn!("Unable to delete {moved_to}:\n{err}");
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Undoes every recorded step in reverse order and puts back the footprint as it was before the
+     * plan started. Rolling back continues past individual failures so that as much as possible is
+     * restored. Any failures are returned together, and the steps that couldn't be undone are kept
+     * in the journal so that they can be tried again by [`Journal::recover`].
+     */
+    pub fn rollback(mut self, fs_manager: &mut FSManager) -> Result<()> {
+        let mut failures = vec![];
+        let mut remaining = vec![];
+        while let Some(step) = self.steps.pop() {
+            debug!("undoing step: {step}");
+            if let Err(err) = Journal::undo(&step, fs_manager) {
+                failures.push(format!("Unable to undo {step}: {err}"));
+                remaining.push(step);
+            }
+        }
+        if let Err(err) = fs_manager.restore_footprint(&self.footprint) {
+            failures.push(format!("Unable to restore the footprint: {err}"));
+        }
+
+        if failures.is_empty() {
+            return fs::remove_file(&self.path)
+                .with_context(|| format!("Unable to remove {}", self.path));
+        }
+        remaining.reverse();
+        self.steps = remaining;
+        if let Err(err) = self.save() {
+            failures.push(format!("{err:#}"));
+        }
+        Err(anyhow!(failures.join("\n")))
+    }
+
+    /**
+     * Undoes a single step. Steps are recorded before they're made, so each one checks that its
+     * change actually happened before undoing it.
+     */
+    fn undo(step: &Step, fs_manager: &mut FSManager) -> Result<()> {
+        match step {
+            Step::RemovedSymlink { path, target } => {
+                if path.symlink_metadata().is_err() {
+                    unix::fs::symlink(target, path)?
+                }
+            }
+            Step::BackedUp { backup } => {
+                if backup.backup.symlink_metadata().is_ok() {
+                    fs_manager.restore_backup(backup)?
+                }
+            }
+            Step::Removed { path, moved_to } => {
+                if moved_to.symlink_metadata().is_ok() {
+                    fs::rename(moved_to, path)?
+                }
+            }
+            Step::CreatedDir { path } => {
+                if path
+                    .symlink_metadata()
+                    .is_ok_and(|metadata| metadata.is_dir())
+                {
+                    fs::remove_dir(path)?
+                }
+            }
+            Step::CreatedSymlink { link } => {
+                if is_symlink_to(&link.dest.path, &link.src.path) {
+                    fs_manager.remove_symlink(link)?
+                }
+            }
+            Step::CreatedCopy { link } => ignore_missing(fs_manager.remove_copy(link))?,
+            Step::RemovedHardlink { link } => {
+                if link.dest.path.symlink_metadata().is_err() {
+                    fs_manager.create_hardlink(link)?
+                }
+            }
+            Step::CreatedHardlink { link } => {
+                if is_same_file(&link.dest.path, &link.src.path) {
+                    fs_manager.remove_hardlink(link)?
+                }
+            }
+            Step::RenderedTemplate { path, previous } => {
+                if previous.is_some() || path.exists() {
+                    fs_manager.restore_generated(path, previous)?
+                }
+            }
+            Step::RemovedOutput { path, contents } => {
+                if !path.exists() {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(path, contents)?
+                }
+            }
+            Step::Moved { from, to } => {
+                if from.symlink_metadata().is_err() && to.symlink_metadata().is_ok() {
+                    move_path(to, from)?
+                }
+            }
+            Step::CopiedFile { path } => ignore_missing(fs::remove_file(path))?,
+            Step::WroteFile { path, previous } => match previous {
+                Some(previous) => write_atomic(path, previous)?,
+                None => ignore_missing(fs::remove_file(path))?,
+            },
+            Step::RestoredBackup { backup } => {
+                if backup.backup.symlink_metadata().is_err()
+                    && backup.path.symlink_metadata().is_ok()
+                {
+                    fs_manager.create_backup(backup)?
+                }
+            }
+        };
+        Ok(())
+    }
+}
+
+/** Treats a path that's already gone as successfully removed */
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/** Whether the given path is a symlink pointing at the given target */
+fn is_symlink_to(path: &Utf8Path, target: &Utf8Path) -> bool {
+    fs::read_link(path).is_ok_and(|link| link == target)
+}
+
+/** Whether both paths are hard links to the same file */
+fn is_same_file(path: &Utf8Path, other: &Utf8Path) -> bool {
+    match (path.symlink_metadata(), other.metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod journal {
+        use crate::footprint::FootprintBackup;
+        use crate::plan::journal::{Journal, Step};
+        use crate::plan::links::Link;
+        use camino::Utf8PathBuf;
+
+        #[test]
+        fn it_should_read_back_every_step_it_writes() {
+            let journal = Journal {
+                timestamp: String::from("2024-01-01T00:00:00Z"),
+                footprint: Some(String::from("version = 2\nlinks = []\n")),
+                steps: vec![
+                    Step::BackedUp {
+                        backup: FootprintBackup {
+                            path: Utf8PathBuf::from("/home/.bashrc"),
+                            created: String::from("2024-01-01T00:00:00Z"),
+                            backup: Utf8PathBuf::from("/home/.dots/.backups/.bashrc"),
+                        },
+                    },
+                    Step::CreatedDir {
+                        path: Utf8PathBuf::from("/home/.config"),
+                    },
+                    Step::CreatedSymlink {
+                        link: Link::new("/home/.dots/example_dot/bashrc", "/home/.bashrc"),
+                    },
+                    Step::RenderedTemplate {
+                        path: Utf8PathBuf::from("/home/.dots/.generated/example_dot/gitconfig"),
+                        previous: None,
+                    },
+                ],
+                path: Utf8PathBuf::new(),
+            };
+
+            let contents = toml::to_string(&journal).unwrap();
+            let parsed: Journal = toml::from_str(&contents).unwrap();
+            assert_eq!(parsed.footprint, journal.footprint);
+            assert_eq!(
+                parsed.steps.iter().map(Step::to_string).collect::<Vec<_>>(),
+                journal
+                    .steps
+                    .iter()
+                    .map(Step::to_string)
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+}
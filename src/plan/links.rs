@@ -1,5 +1,5 @@
 use camino::{Utf8Path, Utf8PathBuf};
-use std::{fmt, fs};
+use std::{fmt, fs, os::unix::fs::MetadataExt};
 
 /*=======*\
 *  Links  *
@@ -11,6 +11,8 @@ pub struct Link {
     pub src: Anchor,
     /// The the path to the symlink
     pub dest: Anchor,
+    /// How the dotfile is placed at the destination
+    pub mode: LinkMode,
 }
 
 impl Link {
@@ -21,12 +23,74 @@ impl Link {
         Link {
             src: Anchor::new_src(src),
             dest: Anchor::new_dest(dest),
+            mode: LinkMode::default(),
         }
     }
 
+    pub fn with_mode(mut self, mode: LinkMode) -> Link {
+        self.mode = mode;
+        self
+    }
+
+    /**
+     * Whether the destination is currently linked to the source. For copies this only checks that
+     * there is a regular file at the destination, use the footprint to check its contents.
+     */
     pub fn exists(&self) -> bool {
-        let Ok(path) = fs::read_link(&self.dest.path) else { return false };
-        path == self.src.path
+        match self.mode {
+            LinkMode::Symlink => {
+                let Ok(path) = fs::read_link(&self.dest.path) else {
+                    return false;
+                };
+                path == self.src.path
+            }
+            LinkMode::Copy => match self.dest.path.symlink_metadata() {
+                Ok(metadata) => metadata.is_file(),
+                Err(_) => false,
+            },
+            LinkMode::Hardlink => is_same_file(&self.src.path, &self.dest.path),
+        }
+    }
+}
+
+/** Whether both paths point to the same file on the same device, without following symlinks */
+pub fn is_same_file<P, Q>(a: P, b: Q) -> bool
+where
+    P: AsRef<Utf8Path>,
+    Q: AsRef<Utf8Path>,
+{
+    let (Ok(a), Ok(b)) = (a.as_ref().symlink_metadata(), b.as_ref().symlink_metadata()) else {
+        return false;
+    };
+    a.is_file() && a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// The different ways that a dotfile can be placed at its destination
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkMode {
+    /// The destination is a symlink to the dotfile
+    #[default]
+    Symlink,
+    /// The destination is a copy of the dotfile, for programs that don't play well with symlinks
+    Copy,
+    /// The destination is a hard link to the dotfile. Both must be on the same filesystem.
+    Hardlink,
+}
+
+impl LinkMode {
+    pub fn is_symlink(&self) -> bool {
+        matches!(self, LinkMode::Symlink)
+    }
+}
+
+impl fmt::Display for LinkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkMode::Symlink => write!(f, "symlink"),
+            LinkMode::Copy => write!(f, "copy"),
+            LinkMode::Hardlink => write!(f, "hardlink"),
+        }
     }
 }
 
@@ -36,6 +100,41 @@ impl fmt::Debug for Link {
     }
 }
 
+/// A link that was left out of the install plan, along with the reason why
+#[derive(PartialEq, Eq, Clone)]
+pub struct SkippedLink {
+    pub link: Link,
+    pub reason: String,
+}
+
+/// How a skipped link is described in JSON output
+#[derive(Serialize)]
+pub struct SkippedLinkOutput<'a> {
+    src: &'a Utf8Path,
+    dest: &'a Utf8Path,
+    reason: &'a str,
+}
+
+impl SkippedLink {
+    pub fn output(&self) -> SkippedLinkOutput<'_> {
+        SkippedLinkOutput {
+            src: &self.link.src.path,
+            dest: &self.link.dest.path,
+            reason: &self.reason,
+        }
+    }
+}
+
+impl fmt::Display for SkippedLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "- {} => {} (skipped, {})",
+            self.link.dest.path, self.link.src.path, self.reason
+        )
+    }
+}
+
 /*=========*\
 *  Anchors  *
 \*=========*/
@@ -90,7 +189,10 @@ impl fmt::Display for AnchorKind {
 #[cfg(test)]
 mod tests {
     mod link {
-        use crate::plan::links::{AnchorKind, Link};
+        use crate::plan::links::{AnchorKind, Link, LinkMode};
+        use camino::Utf8Path;
+        use std::fs;
+        use tempfile::tempdir;
 
         #[test]
         fn it_should_create_a_new_link() {
@@ -101,6 +203,24 @@ mod tests {
             assert_eq!(link.dest.kind, AnchorKind::Destination);
         }
 
+        #[test]
+        fn it_should_compare_inodes_for_hardlinks() {
+            let dir = tempdir().unwrap();
+            let dir = Utf8Path::from_path(dir.path()).unwrap();
+            let src = dir.join("src.txt");
+            let dest = dir.join("dest.txt");
+            let other = dir.join("other.txt");
+            fs::write(&src, "contents").unwrap();
+            fs::write(&other, "contents").unwrap();
+            fs::hard_link(&src, &dest).unwrap();
+
+            let link = Link::new(&src, &dest).with_mode(LinkMode::Hardlink);
+            assert!(link.exists());
+
+            let link = Link::new(&src, &other).with_mode(LinkMode::Hardlink);
+            assert!(!link.exists());
+        }
+
         #[test]
         fn it_should_display_correctly_when_printed() {
             let link = Link::new("./src.txt", "./dest.txt");
@@ -1,5 +1,10 @@
-pub use self::plan::Plan;
+pub use self::plan::{execute, Plan, PlanError};
 
+pub mod actions;
+pub mod confirm;
+pub mod diff;
+pub mod journal;
 pub mod links;
+#[allow(clippy::module_inception)]
 pub mod plan;
 pub mod resolve;
@@ -1,21 +1,31 @@
-use crate::dots::{Dot, Environment};
+use crate::dots::{BrokenDot, BrokenDotOutput, Dot, Environment};
+use crate::footprint::{Footprint, LinkRecord};
 use crate::fs_manager::FSManager;
-use crate::plan::resolve::{ResolveIssueKind, ResolvedLink};
+use crate::output::Format;
+use crate::plan::resolve::{ResolveIssueKind, ResolvedLink, ResolvedLinkOutput};
 use anyhow::Result;
-use camino::Utf8Path;
-use std::fs;
+use camino::{Utf8Path, Utf8PathBuf};
 use std::{
+    collections::BTreeMap,
     fmt::{self, Display},
     io,
+    ops::Range,
 };
+use utils::text::indent;
 
-use super::links::Link;
+use super::actions::{Action, Fingerprint, PlannedLink};
+use super::confirm::{Decision, Prompt};
+use super::diff::{differences, is_in_the_way};
+use super::journal::Journal;
+use super::links::{Link, LinkMode, SkippedLink, SkippedLinkOutput};
 use super::resolve::{ResolveIssue, ResolveIssueLevel};
 
 mod styles {
     use utils::stylize::Style;
 
     pub const TITLE: Style = Style::new().bold();
+    pub const SKIPPED: Style = Style::new().dim();
+    pub const BROKEN: Style = Style::new().red();
 }
 
 #[derive(Debug)]
@@ -49,46 +59,284 @@ impl std::error::Error for PlanError {
 
 pub struct Plan {
     force: bool,
+    format: Format,
     links: Vec<ResolvedLink>,
+    dots: Vec<PlannedDot>,
+    /// Dots that couldn't be loaded, whose links are left alone
+    broken: Vec<BrokenDot>,
+    /// Whether to show how files in the way of links differ from the dotfiles
+    diff: bool,
+    /// Used to ask what to do with destinations that already exist, instead of requiring --force
+    prompt: Option<Prompt>,
+    /// What the user chose to do with each existing destination, by its resolved path
+    decisions: BTreeMap<Utf8PathBuf, Decision>,
+    /// The names of the dots to plan. Every dot is planned when this is `None`.
+    only: Option<Vec<String>>,
+    /// Destinations linked by dots that aren't being planned, which planned links can't reuse
+    claimed: Vec<Utf8PathBuf>,
+    /// The state of each planned destination before anything was cleaned, by its resolved path
+    fingerprints: BTreeMap<Utf8PathBuf, Fingerprint>,
+}
+
+/// The links in the plan that belong to a single dot
+struct PlannedDot {
+    name: String,
+    links: Range<usize>,
+    skipped: Vec<SkippedLink>,
+}
+
+/// How a plan is described in JSON output
+#[derive(Serialize)]
+pub struct PlanOutput<'a> {
+    dots: Vec<PlannedDotOutput<'a>>,
+    broken: Vec<BrokenDotOutput<'a>>,
+}
+
+#[derive(Serialize)]
+struct PlannedDotOutput<'a> {
+    name: &'a str,
+    links: Vec<ResolvedLinkOutput<'a>>,
+    skipped: Vec<SkippedLinkOutput<'a>>,
 }
 
 impl Plan {
     pub fn new(force: bool) -> Plan {
         Plan {
             force,
+            format: Format::Text,
             links: vec![],
+            dots: vec![],
+            broken: vec![],
+            diff: false,
+            prompt: None,
+            decisions: BTreeMap::new(),
+            only: None,
+            claimed: vec![],
+            fingerprints: BTreeMap::new(),
+        }
+    }
+
+    /**
+     * Limits the plan to the given dots. The links of every other dot are left as they are, but
+     * planned links still can't share a destination with them.
+     */
+    pub fn with_only(mut self, names: Vec<String>) -> Plan {
+        self.only = Some(names);
+        self
+    }
+
+    /** The names of the dots the plan is limited to, if it was limited with [`Plan::with_only`] */
+    pub fn only(&self) -> Option<&[String]> {
+        self.only.as_deref()
+    }
+
+    /** Shows how each file that's in the way of a link differs from the dotfile */
+    pub fn with_diff(mut self, diff: bool) -> Plan {
+        self.diff = diff;
+        self
+    }
+
+    /** Asks what to do with each destination that already exists while the plan is validated */
+    pub fn with_prompt(mut self, prompt: Prompt) -> Plan {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /** Sets the dots that couldn't be loaded, so they can be reported and their links kept */
+    pub fn with_broken(mut self, broken: Vec<BrokenDot>) -> Plan {
+        self.broken = broken;
+        self
+    }
+
+    /** Sets how the plan is reported while it's being validated */
+    pub fn with_format(mut self, format: Format) -> Plan {
+        self.format = format;
+        self
+    }
+
+    pub fn output(&self) -> PlanOutput<'_> {
+        let dots = self.dots.iter().map(|dot| PlannedDotOutput {
+            name: &dot.name,
+            links: self.links[dot.links.clone()]
+                .iter()
+                .map(ResolvedLink::output)
+                .collect(),
+            skipped: dot.skipped.iter().map(SkippedLink::output).collect(),
+        });
+        PlanOutput {
+            dots: dots.collect(),
+            broken: self.broken.iter().map(BrokenDot::output).collect(),
+        }
+    }
+
+    /**
+     * Removes anything the planned dots no longer link, recording each removal in the given
+     * [`Journal`]. Cleaning can remove files from link destinations, so the destinations of every
+     * link that was dropped from the footprint are resolved again. Each destination's fingerprint
+     * is taken first, since a saved plan is checked against the fs as it was before cleaning.
+     */
+    pub fn clean(
+        &mut self,
+        env: &Environment,
+        journal: &mut Journal,
+        fs_manager: &mut FSManager,
+        dots: &mut [Dot],
+    ) -> Result<()> {
+        let dests: Vec<Utf8PathBuf> = dots
+            .iter()
+            .filter(|dot| self.is_planned(dot))
+            .flat_map(|dot| &dot.links)
+            .filter_map(|link| link.dest.path.clone())
+            .collect();
+        for dest in dests {
+            let fingerprint = Fingerprint::of(&dest)?;
+            self.fingerprints.insert(dest, fingerprint);
+        }
+
+        let tracked = fs_manager.footprint().links.clone();
+        self.clean_dots(env, journal, fs_manager, dots)?;
+
+        let dropped: Vec<&Utf8PathBuf> = tracked
+            .iter()
+            .filter(|link| !fs_manager.footprint().links.contains(link))
+            .map(|link| &link.dest.path)
+            .collect();
+        for link in dots.iter_mut().flat_map(|dot| &mut dot.links) {
+            if link
+                .dest
+                .path
+                .as_ref()
+                .is_some_and(|dest| dropped.contains(&dest))
+            {
+                link.resolve_dest_again();
+            }
         }
+        Ok(())
     }
 
-    pub fn clean(&self, env: &Environment, fs_manager: &mut FSManager, dots: &[Dot]) -> Result<()> {
+    fn clean_dots(
+        &self,
+        env: &Environment,
+        journal: &mut Journal,
+        fs_manager: &mut FSManager,
+        dots: &[Dot],
+    ) -> Result<()> {
+        let dots: Vec<&Dot> = dots.iter().filter(|dot| self.is_planned(dot)).collect();
         let links: Vec<Link> = dots
             .iter()
             .flat_map(|dot| &dot.links)
             .filter_map(|resolved_link| resolved_link.as_link())
             .collect();
-        fs_manager.clean(&links, env)?;
+        let only = self.only.as_deref();
+        fs_manager.clean(journal, &links, &self.broken, only, env)?;
+
+        let outputs: Vec<Utf8PathBuf> = dots
+            .iter()
+            .flat_map(|dot| &dot.links)
+            .filter_map(|resolved_link| resolved_link.template.as_ref())
+            .map(|template| template.output.clone())
+            .collect();
+        fs_manager.clean_generated(journal, &outputs, &self.broken, only, env)?;
         Ok(())
     }
 
-    pub fn validate(&mut self, dots: Vec<Dot>) -> Result<(), PlanError> {
+    /** Whether the given dot was selected to be planned */
+    fn is_planned(&self, dot: &Dot) -> bool {
+        match &self.only {
+            Some(names) => names.contains(&dot.package.name),
+            None => true,
+        }
+    }
+
+    pub fn validate(&mut self, dots: Vec<Dot>, footprint: &Footprint) -> Result<(), PlanError> {
         let mut suggest_force = false;
+        let mut suggest_pull = false;
         let mut fixed_issues: Vec<&ResolveIssue> = vec![];
+        let is_text = self.format == Format::Text;
+        let (dots, others): (Vec<Dot>, Vec<Dot>) =
+            dots.into_iter().partition(|dot| self.is_planned(dot));
+        self.claimed = others
+            .iter()
+            .flat_map(|dot| &dot.links)
+            .filter_map(|link| link.dest.path.clone())
+            .collect();
+
         for dot in dots {
-            let title = format!("[{name}]", name = &dot.package.name);
-            eprintln!("\n{title}", title = styles::TITLE.apply(title));
+            if is_text {
+                let title = format!("[{name}]", name = &dot.package.name);
+                eprintln!("\n{title}", title = styles::TITLE.apply(title));
+            }
             let links = dot.links;
+            let start = self.links.len();
 
             for mut link in links {
-                if let Some(resolved_dest) = &link.dest.path {
-                    let duplicates = self.duplicates(resolved_dest);
-                    if !duplicates.is_empty() {
+                if let Some(resolved_dest) = link.dest.path.clone() {
+                    let duplicates = self.duplicates(&resolved_dest);
+                    if !duplicates.is_empty() || self.is_claimed(&resolved_dest) {
                         link.dest.mark_as_duplicate();
                     }
+
+                    if link.mode == LinkMode::Copy {
+                        match footprint.is_copy_modified(&resolved_dest) {
+                            Some(false) => link.dest.mark_as_own_copy(),
+                            Some(true) => link.dest.mark_as_modified(),
+                            None => {}
+                        }
+                    }
                 }
 
-                eprintln!("{link}");
+                if is_text {
+                    eprintln!("{link}");
+                    if self.diff && is_in_the_way(&link) {
+                        if let Some(differences) = differences(&link) {
+                            eprintln!("{}", indent(2, &differences));
+                        }
+                    }
+                }
                 self.links.push(link);
             }
+
+            if is_text {
+                for skipped in &dot.skipped {
+                    eprintln!("{}", styles::SKIPPED.apply(skipped));
+                }
+            }
+
+            self.dots.push(PlannedDot {
+                name: dot.package.name,
+                links: start..self.links.len(),
+                skipped: dot.skipped,
+            });
+        }
+
+        if is_text {
+            for dot in &self.broken {
+                let title = format!("[{name}]", name = &dot.name);
+                eprintln!("\n{title}", title = styles::TITLE.apply(title));
+                eprintln!("{}", styles::BROKEN.apply(format!("✖ {}", dot.error)));
+                let note = "- its links will be left as they are until the Dot.toml is fixed";
+                eprintln!("{}", styles::SKIPPED.apply(note));
+            }
+        }
+
+        if let Some(mut prompt) = self.prompt.take() {
+            if !self.force && !self.has_errors() {
+                self.confirm(&mut prompt)?;
+            }
+        }
+        for link in &self.links {
+            let is_answered = link
+                .dest
+                .path
+                .as_ref()
+                .is_some_and(|dest| self.decisions.contains_key(dest));
+            if is_answered {
+                fixed_issues.extend(
+                    link.issues()
+                        .into_iter()
+                        .filter(|issue| matches!(issue.kind, ResolveIssueKind::AlreadyExists(_))),
+                );
+            }
         }
 
         let issues = self.issues();
@@ -97,22 +345,30 @@ impl Plan {
             let existing_file_issues: Vec<&ResolveIssue> = self
                 .issues()
                 .into_iter()
-                .filter(|&issue| matches!(issue.kind, ResolveIssueKind::AlreadyExists(_)))
+                .filter(|&issue| {
+                    matches!(
+                        issue.kind,
+                        ResolveIssueKind::AlreadyExists(_) | ResolveIssueKind::Modified
+                    )
+                })
                 .collect();
 
             let has_existing_files = !existing_file_issues.is_empty();
+            suggest_pull = existing_file_issues
+                .iter()
+                .any(|issue| issue.kind == ResolveIssueKind::Modified);
 
             if self.force {
                 for issue in existing_file_issues {
                     fixed_issues.push(issue);
                 }
+            } else if has_existing_files {
+                suggest_force = existing_file_issues
+                    .iter()
+                    .any(|issue| !fixed_issues.contains(issue));
             }
 
-            if !self.force && has_existing_files {
-                suggest_force = true;
-            }
-
-            if issues.len() > fixed_issues.len() {
+            if is_text && issues.len() > fixed_issues.len() {
                 eprintln!();
             }
 
@@ -129,11 +385,18 @@ impl Plan {
             }
         }
 
-        eprintln!();
+        if is_text {
+            eprintln!();
+        }
 
         if suggest_force {
             info!("use --force to overwrite existing directories");
-            eprintln!();
+            if suggest_pull {
+                info!("use `dots pull <path>` to copy changes to a copied file back into its dot");
+            }
+            if is_text {
+                eprintln!();
+            }
         }
 
         if self.has_errors() {
@@ -150,45 +413,90 @@ impl Plan {
         }
     }
 
-    pub fn execute(&self, fs_manager: &mut FSManager, force: bool) -> Result<()> {
-        let links: Vec<Link> = self
-            .links
-            .iter()
-            .filter_map(|resolved_link| resolved_link.as_link())
-            .collect();
-
-        for link in links {
-            if link.dest.path.is_symlink() {
-                fs_manager.remove_symlink(&link)?;
-            } else if link.dest.path.is_file() {
-                if !force {
-                    return Err(anyhow::Error::new(io::Error::new(
-                        io::ErrorKind::AlreadyExists,
-                        "Destination already Exists!",
-                    )));
-                }
-
-                fs::remove_file(&link.dest.path)?;
-            } else if link.dest.path.is_dir() {
-                if !force {
-                    return Err(anyhow::Error::new(io::Error::new(
-                        io::ErrorKind::AlreadyExists,
-                        "Destination already Exists!",
-                    )));
-                }
-
-                fs::remove_dir_all(&link.dest.path)?;
+    /**
+     * Asks what to do with each destination that already exists. Fails if the user aborts, in
+     * which case nothing has been changed yet.
+     */
+    fn confirm(&mut self, prompt: &mut Prompt) -> Result<(), PlanError> {
+        for link in &self.links {
+            let Some(dest) = &link.dest.path else {
+                continue;
+            };
+            if !is_in_the_way(link) || link.has_errors() {
+                continue;
             }
 
-            if let Some(parent) = link.dest.path.parent() {
-                fs::create_dir_all(parent)?;
+            let decision = prompt.ask(link).map_err(|err| {
+                PlanError::new(&format!("Unable to ask what to do with {dest}: {err}"))
+            })?;
+            if decision == Decision::Abort {
+                return Err(PlanError::new("Install was aborted."));
             }
-
-            fs_manager.create_symlink(&link)?;
+            self.decisions.insert(dest.clone(), decision);
         }
         Ok(())
     }
 
+    /**
+     * Decides what needs to happen at each destination based on what is currently there. Only
+     * meaningful once the plan has been validated.
+     */
+    pub fn actions(&self) -> Result<Vec<PlannedLink>> {
+        let mut planned = vec![];
+        for dot in &self.dots {
+            for resolved_link in &self.links[dot.links.clone()] {
+                let Some(link) = resolved_link.as_link() else {
+                    continue;
+                };
+                let decision = self.decisions.get(&link.dest.path);
+                let action = if decision == Some(&Decision::Skip) {
+                    Action::Skip
+                } else if link.dest.path.is_symlink() {
+                    Action::ReplaceSymlink
+                } else if link.mode == LinkMode::Copy
+                    && link.dest.path.is_file()
+                    && !resolved_link.dest.has_issues()
+                {
+                    Action::ReplaceCopy
+                } else if link.mode == LinkMode::Hardlink && link.exists() {
+                    Action::ReplaceHardlink
+                } else if decision == Some(&Decision::Overwrite) {
+                    Action::Overwrite
+                } else if decision == Some(&Decision::Backup) {
+                    Action::ConfirmedBackup
+                } else if link.dest.path.exists() {
+                    Action::Backup
+                } else {
+                    Action::Create
+                };
+
+                let fingerprint = match self.fingerprints.get(&link.dest.path) {
+                    Some(fingerprint) => fingerprint.clone(),
+                    None => Fingerprint::of(&link.dest.path)?,
+                };
+                planned.push(PlannedLink {
+                    dot: dot.name.clone(),
+                    key: resolved_link.dest.original.path.to_string(),
+                    fingerprint,
+                    template: resolved_link.template.clone(),
+                    link,
+                    action,
+                });
+            }
+        }
+        Ok(planned)
+    }
+
+    /** Links everything in the plan. See [`execute`] */
+    pub fn execute(
+        &self,
+        journal: &mut Journal,
+        fs_manager: &mut FSManager,
+        force: bool,
+    ) -> Result<()> {
+        execute(&self.actions()?, journal, fs_manager, force)
+    }
+
     fn duplicates(&self, path: &Utf8Path) -> Vec<&ResolvedLink> {
         self.links
             .iter()
@@ -196,6 +504,11 @@ impl Plan {
             .collect()
     }
 
+    /** Whether a dot that isn't being planned links the given destination */
+    fn is_claimed(&self, path: &Utf8Path) -> bool {
+        self.claimed.iter().any(|claimed| claimed == path)
+    }
+
     fn issues(&self) -> Vec<&ResolveIssue> {
         self.links.iter().flat_map(|link| link.issues()).collect()
     }
@@ -223,3 +536,76 @@ impl Plan {
             .collect()
     }
 }
+
+/**
+ * Links everything that was planned. Every change is recorded in the given [`Journal`] so that if
+ * any step fails, it can be rolled back along with everything else in the journal.
+ */
+pub fn execute(
+    planned: &[PlannedLink],
+    journal: &mut Journal,
+    fs_manager: &mut FSManager,
+    force: bool,
+) -> Result<()> {
+    let result = execute_steps(planned, journal, fs_manager, force);
+    if result.is_err() {
+        warn!("Install failed part way through, rolling back changes...");
+    }
+    result
+}
+
+fn execute_steps(
+    planned: &[PlannedLink],
+    journal: &mut Journal,
+    fs_manager: &mut FSManager,
+    force: bool,
+) -> Result<()> {
+    for PlannedLink {
+        dot,
+        key,
+        link,
+        template,
+        action,
+        ..
+    } in planned
+    {
+        if *action == Action::Skip {
+            continue;
+        }
+        if let Some(template) = template {
+            journal.render_template(fs_manager, template)?;
+        }
+
+        match action {
+            Action::Create => {}
+            Action::ReplaceSymlink => journal.remove_symlink(&link.dest.path)?,
+            Action::ReplaceCopy => journal.remove_path(&link.dest.path)?,
+            Action::ReplaceHardlink => journal.remove_hardlink(fs_manager, link)?,
+            Action::Backup => {
+                if !force {
+                    return Err(anyhow::Error::new(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        "Destination already Exists!",
+                    )));
+                }
+
+                journal.backup_path(fs_manager, &link.dest.path)?;
+            }
+            Action::ConfirmedBackup => journal.backup_path(fs_manager, &link.dest.path)?,
+            Action::Overwrite => journal.remove_path(&link.dest.path)?,
+            Action::Skip => unreachable!("skipped links are never linked"),
+        }
+
+        if let Some(parent) = link.dest.path.parent() {
+            journal.create_dir_all(parent)?;
+        }
+
+        let record = LinkRecord::new(dot, key);
+        match link.mode {
+            LinkMode::Symlink => journal.create_symlink(fs_manager, link, record)?,
+            LinkMode::Copy => journal.create_copy(fs_manager, link, record)?,
+            LinkMode::Hardlink => journal.create_hardlink(fs_manager, link, record)?,
+        }
+    }
+    Ok(())
+}
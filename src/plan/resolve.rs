@@ -1,10 +1,12 @@
-use crate::plan::links::{Anchor, AnchorKind, Link};
+use crate::plan::links::{is_same_file, Anchor, AnchorKind, Link, LinkMode};
+use crate::template::{self, RenderedTemplate};
 use crate::utils::fs::{canonicalize, home};
 use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::fs::FileType;
 use std::path::PathBuf;
-use std::{fmt, fs, io, process};
+use std::{fmt, fs, io};
 
 mod styles {
     use utils::stylize::Style;
@@ -21,20 +23,70 @@ pub fn resolve<P>(root: P, link: Link) -> ResolvedLink
 where
     P: AsRef<Utf8Path>,
 {
-    let src = resolve_src(link.src, &root);
-    let dest = resolve_dest(link.dest, &src);
-    ResolvedLink { src, dest }
+    let src = resolve_src(link.src, &root, link.mode);
+    let dest = resolve_dest(link.dest, &src, link.mode, None);
+    ResolvedLink {
+        src,
+        dest,
+        mode: link.mode,
+        template: None,
+    }
 }
 
-fn resolve_src<P>(anchor: Anchor, root: P) -> ResolvedAnchor
+/**
+ * Resolves a link whose source is a template. The template is rendered with the given variables
+ * and the link is pointed at the output path that the rendered file will be written to.
+ */
+pub fn resolve_template<P>(
+    root: P,
+    link: Link,
+    output: Utf8PathBuf,
+    variables: &BTreeMap<String, String>,
+) -> ResolvedLink
 where
     P: AsRef<Utf8Path>,
 {
-    if anchor.kind != AnchorKind::Source {
-        error!("Invalid AnchorKind passed to resolve_src");
-        process::exit(1);
+    let mut src = resolve_src(link.src, &root, link.mode);
+    let mut rendered = None;
+
+    if let Some(path) = src.path.take() {
+        match fs::read_to_string(&path) {
+            Ok(contents) => match template::render(&contents, variables) {
+                Ok(contents) => {
+                    rendered = Some(RenderedTemplate {
+                        output: output.clone(),
+                        contents,
+                    });
+                    src.path = Some(output);
+                }
+                Err(msg) => src.issues.push(ResolveIssue::new(
+                    &src.original,
+                    ResolveIssueKind::Template(msg),
+                )),
+            },
+            Err(err) => src.issues.push(ResolveIssue::io(&src.original, err)),
+        }
     }
 
+    let dest = resolve_dest(link.dest, &src, link.mode, rendered.as_ref());
+    ResolvedLink {
+        src,
+        dest,
+        mode: link.mode,
+        template: rendered,
+    }
+}
+
+fn resolve_src<P>(anchor: Anchor, root: P, mode: LinkMode) -> ResolvedAnchor
+where
+    P: AsRef<Utf8Path>,
+{
+    debug_assert_eq!(
+        anchor.kind,
+        AnchorKind::Source,
+        "Invalid AnchorKind passed to resolve_src"
+    );
+
     let root = root.as_ref();
     let mut src = ResolvedAnchor::new(anchor);
     if src.original.path.is_absolute() {
@@ -49,7 +101,15 @@ where
     let absolute_path = root.join(&src.original.path);
 
     match canonicalize(absolute_path) {
-        Ok(path) => src.path = Some(path),
+        Ok(path) => {
+            if !mode.is_symlink() && path.is_dir() {
+                src.issues.push(ResolveIssue::new(
+                    &src.original,
+                    ResolveIssueKind::InvalidPath(format!("Only files can use the {mode} mode.")),
+                ));
+            }
+            src.path = Some(path)
+        }
         Err(err) => {
             use self::ResolveIssueKind as link;
             use std::io::ErrorKind as io;
@@ -67,11 +127,17 @@ where
     src
 }
 
-fn resolve_dest(anchor: Anchor, src: &ResolvedAnchor) -> ResolvedAnchor {
-    if anchor.kind != AnchorKind::Destination {
-        error!("Invalid AnchorKind passed to resolve_dest");
-        process::exit(1);
-    }
+fn resolve_dest(
+    anchor: Anchor,
+    src: &ResolvedAnchor,
+    mode: LinkMode,
+    rendered: Option<&RenderedTemplate>,
+) -> ResolvedAnchor {
+    debug_assert_eq!(
+        anchor.kind,
+        AnchorKind::Destination,
+        "Invalid AnchorKind passed to resolve_dest"
+    );
 
     let mut dest = ResolvedAnchor::new(anchor);
 
@@ -108,6 +174,8 @@ fn resolve_dest(anchor: Anchor, src: &ResolvedAnchor) -> ResolvedAnchor {
                             }
                             Err(_) => None,
                         }
+                    } else if is_linked(path, src, mode, rendered) {
+                        None
                     } else {
                         Some(ResolveIssue::new(
                             &dest.original,
@@ -126,6 +194,33 @@ fn resolve_dest(anchor: Anchor, src: &ResolvedAnchor) -> ResolvedAnchor {
     dest
 }
 
+/** Whether a copy or hard link is already in place at the given destination */
+fn is_linked(
+    path: &Utf8Path,
+    src: &ResolvedAnchor,
+    mode: LinkMode,
+    rendered: Option<&RenderedTemplate>,
+) -> bool {
+    match mode {
+        LinkMode::Symlink => false,
+        LinkMode::Copy => path.is_file() && is_copy_of(path, src, rendered),
+        LinkMode::Hardlink => src.path.as_ref().is_some_and(|src| is_same_file(src, path)),
+    }
+}
+
+/** Whether the given file already has the same contents as the source it would be copied from */
+fn is_copy_of(path: &Utf8Path, src: &ResolvedAnchor, rendered: Option<&RenderedTemplate>) -> bool {
+    let expected = match (rendered, &src.path) {
+        (Some(template), _) => template.contents.as_bytes().to_vec(),
+        (None, Some(src_path)) => match fs::read(src_path) {
+            Ok(contents) => contents,
+            Err(_) => return false,
+        },
+        (None, None) => return false,
+    };
+    fs::read(path).is_ok_and(|contents| contents == expected)
+}
+
 /*================*\
 *  Resolved Links  *
 \*================*/
@@ -137,6 +232,20 @@ pub struct ResolvedLink {
     pub src: ResolvedAnchor,
     /// The resolved anchor for the symlink
     pub dest: ResolvedAnchor,
+    /// How the dotfile will be placed at the destination
+    pub mode: LinkMode,
+    /// The rendered output if the source is a template
+    pub template: Option<RenderedTemplate>,
+}
+
+/// How a resolved link is described in JSON output
+#[derive(Serialize)]
+pub struct ResolvedLinkOutput<'a> {
+    src: ResolvedAnchorOutput<'a>,
+    dest: ResolvedAnchorOutput<'a>,
+    mode: LinkMode,
+    /// Whether the source is a template
+    template: bool,
 }
 
 impl ResolvedLink {
@@ -154,13 +263,38 @@ impl ResolvedLink {
     pub fn has_warnings(&self) -> bool {
         self.src.has_errors() | self.dest.has_warnings()
     }
+
+    pub fn output(&self) -> ResolvedLinkOutput<'_> {
+        ResolvedLinkOutput {
+            src: self.src.output(),
+            dest: self.dest.output(),
+            mode: self.mode,
+            template: self.template.is_some(),
+        }
+    }
+
+    /** Resolves the destination again, for when whatever was at it has changed */
+    pub fn resolve_dest_again(&mut self) {
+        self.dest = resolve_dest(
+            self.dest.original.clone(),
+            &self.src,
+            self.mode,
+            self.template.as_ref(),
+        );
+    }
+
     /// Returns a simplified link if all paths are valid
     pub fn as_link(&self) -> Option<Link> {
-        let Some(src) = &self.src.path else { return None };
-        let Some(dest) = &self.dest.path else { return None };
+        let Some(src) = &self.src.path else {
+            return None;
+        };
+        let Some(dest) = &self.dest.path else {
+            return None;
+        };
         Some(Link {
             src: Anchor::new_src(src),
             dest: Anchor::new_dest(dest),
+            mode: self.mode,
         })
     }
 }
@@ -200,7 +334,19 @@ impl Display for ResolvedLink {
             None => dest_path,
         };
 
-        write!(f, "{} {} => {}", statusmark, dest_msg, src_msg)
+        write!(f, "{} {} => {}", statusmark, dest_msg, src_msg)?;
+
+        let mut notes = vec![];
+        if self.template.is_some() {
+            notes.push(String::from("template"));
+        }
+        if !self.mode.is_symlink() {
+            notes.push(self.mode.to_string());
+        }
+        if !notes.is_empty() {
+            write!(f, " ({})", notes.join(", "))?;
+        }
+        Ok(())
     }
 }
 
@@ -220,6 +366,14 @@ pub struct ResolvedAnchor {
     pub issues: Vec<ResolveIssue>,
 }
 
+/// How a resolved anchor is described in JSON output
+#[derive(Serialize)]
+pub struct ResolvedAnchorOutput<'a> {
+    original: &'a Utf8Path,
+    path: Option<&'a Utf8Path>,
+    issues: Vec<ResolveIssueOutput>,
+}
+
 impl ResolvedAnchor {
     fn new(original: Anchor) -> Self {
         ResolvedAnchor {
@@ -229,6 +383,14 @@ impl ResolvedAnchor {
         }
     }
 
+    pub fn output(&self) -> ResolvedAnchorOutput<'_> {
+        ResolvedAnchorOutput {
+            original: &self.original.path,
+            path: self.path.as_deref(),
+            issues: self.issues.iter().map(ResolveIssue::output).collect(),
+        }
+    }
+
     pub fn kind(&self) -> &AnchorKind {
         &self.original.kind
     }
@@ -253,6 +415,24 @@ impl ResolvedAnchor {
         self.issues.iter().map(|issue| issue.level()).max()
     }
 
+    /**
+     * Used once a copied destination is known to be an untouched copy made by dots, which makes
+     * it safe to replace.
+     */
+    pub fn mark_as_own_copy(&mut self) {
+        self.issues
+            .retain(|issue| !matches!(issue.kind, ResolveIssueKind::AlreadyExists(_)));
+    }
+
+    /** Used once a copied destination is known to have been changed since dots copied it */
+    pub fn mark_as_modified(&mut self) {
+        for issue in &mut self.issues {
+            if matches!(issue.kind, ResolveIssueKind::AlreadyExists(_)) {
+                issue.kind = ResolveIssueKind::Modified;
+            }
+        }
+    }
+
     pub fn mark_as_duplicate(&mut self) {
         self.issues.push(ResolveIssue::new(
             &self.original,
@@ -285,12 +465,30 @@ pub struct ResolveIssue {
     pub anchor: Anchor,
 }
 
-#[derive(Eq, PartialEq, Ord, PartialOrd)]
+/// How an issue is described in JSON output
+#[derive(Serialize)]
+pub struct ResolveIssueOutput {
+    kind: &'static str,
+    level: ResolveIssueLevel,
+    message: String,
+}
+
+#[derive(Serialize, Eq, PartialEq, Ord, PartialOrd)]
+#[serde(rename_all = "lowercase")]
 pub enum ResolveIssueLevel {
     Error,
     Warning,
 }
 
+impl Display for ResolveIssueLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResolveIssueLevel::Error => write!(f, "error"),
+            ResolveIssueLevel::Warning => write!(f, "warning"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum ResolveIssueKind {
     Conflict,
@@ -298,9 +496,29 @@ pub enum ResolveIssueKind {
     InvalidPath(String),
     NotFound,
     PermissionDenied,
+    Template(String),
+    /// A copied dotfile was changed at its destination since it was last copied
+    Modified,
     IO(io::Error),
 }
 
+impl ResolveIssueKind {
+    /** A stable name for this kind of issue, used in machine readable output */
+    pub fn name(&self) -> &'static str {
+        use self::ResolveIssueKind::*;
+        match self {
+            Conflict => "conflict",
+            AlreadyExists(_) => "already_exists",
+            InvalidPath(_) => "invalid_path",
+            NotFound => "not_found",
+            PermissionDenied => "permission_denied",
+            Template(_) => "template",
+            Modified => "modified",
+            IO(_) => "io",
+        }
+    }
+}
+
 impl Eq for ResolveIssueKind {}
 
 impl PartialEq for ResolveIssueKind {
@@ -308,6 +526,7 @@ impl PartialEq for ResolveIssueKind {
         match (self, other) {
             (Self::AlreadyExists(a), Self::AlreadyExists(b)) => a == b,
             (Self::InvalidPath(a), Self::InvalidPath(b)) => a == b,
+            (Self::Template(a), Self::Template(b)) => a == b,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
@@ -334,9 +553,19 @@ impl ResolveIssue {
             InvalidPath(_) => Error,
             NotFound => Error,
             PermissionDenied => Error,
+            Template(_) => Error,
+            Modified => Warning,
             IO(_) => Error,
         }
     }
+
+    pub fn output(&self) -> ResolveIssueOutput {
+        ResolveIssueOutput {
+            kind: self.kind.name(),
+            level: self.level(),
+            message: self.to_string(),
+        }
+    }
 }
 
 impl Display for ResolveIssue {
@@ -373,6 +602,14 @@ impl Display for ResolveIssue {
                 "Permission denied to {}: {}",
                 self.anchor.kind, self.anchor.path
             ),
+            Modified => write!(
+                f,
+                "{} has been changed since it was copied: {}",
+                self.anchor.kind, self.anchor.path
+            ),
+            Template(ref msg) => {
+                write!(f, "Unable to render template {}: {}", self.anchor.path, msg)
+            }
             IO(ref err) => write!(
                 f,
                 "Error resolving {} {}: {}",
@@ -382,7 +619,7 @@ impl Display for ResolveIssue {
     }
 }
 
-fn file_type_to_str(file_type: &FileType) -> &str {
+pub fn file_type_to_str(file_type: &FileType) -> &str {
     if file_type.is_dir() {
         "a directory"
     } else if file_type.is_symlink() {
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use camino::Utf8Path;
+use std::fs;
+
+/*=========*\
+*  Profile  *
+\*=========*/
+
+/// The profile chosen during the last install, remembered so later runs link the same subset
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SelectedProfile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl SelectedProfile {
+    pub fn read<P>(path: P) -> SelectedProfile
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let path = path.as_ref();
+        let Ok(string) = fs::read_to_string(path) else {
+            return SelectedProfile::default();
+        };
+        toml::from_str(&string).unwrap_or_else(|err| {
+            warn!("Error parsing {path}:\n{err}");
+            SelectedProfile::default()
+        })
+    }
+
+    /** Writes the selected profile, removing the file if no profile is selected */
+    pub fn save<P>(&self, path: P) -> Result<()>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let path = path.as_ref();
+        if self.name.is_none() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use std::fs::{self, File, TryLockError};
+
+use crate::dots::Environment;
+use crate::error::DotsError;
+
+/*===========*\
+*  Root Lock  *
+\*===========*/
+
+/**
+ * An exclusive lock on the dots root, held by commands that change it so that two of them can't
+ * update the footprint at the same time. The lock is released when this is dropped, or by the
+ * operating system if dots exits without dropping it.
+ */
+pub struct RootLock {
+    _file: File,
+}
+
+impl RootLock {
+    /** Takes the lock on the root of the given environment, failing right away if it's held */
+    pub fn acquire(env: &Environment) -> Result<RootLock, DotsError> {
+        RootLock::lock(env.root(), &env.root_lock_path())
+    }
+
+    fn lock(root: Utf8PathBuf, path: &Utf8Path) -> Result<RootLock, DotsError> {
+        fs::create_dir_all(&root)
+            .map_err(|err| DotsError::io(format!("Unable to create {root}:"), err))?;
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(|err| DotsError::io(format!("Unable to open {path}:"), err))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(RootLock { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(DotsError::Locked(root)),
+            Err(TryLockError::Error(err)) => {
+                Err(DotsError::io(format!("Unable to lock {path}:"), err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod root_lock {
+        use crate::error::DotsError;
+        use crate::root_lock::RootLock;
+        use camino::Utf8Path;
+        use tempfile::tempdir;
+
+        #[test]
+        fn it_should_refuse_a_second_lock_until_the_first_is_dropped() {
+            let dir = tempdir().unwrap();
+            let root = Utf8Path::from_path(dir.path()).unwrap().join(".dots");
+            let path = root.join("dot-root.lock");
+
+            let lock = RootLock::lock(root.clone(), &path).unwrap();
+            assert!(matches!(
+                RootLock::lock(root.clone(), &path),
+                Err(DotsError::Locked(_))
+            ));
+
+            drop(lock);
+            assert!(RootLock::lock(root, &path).is_ok());
+        }
+    }
+}
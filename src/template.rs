@@ -0,0 +1,135 @@
+use camino::Utf8PathBuf;
+use std::collections::BTreeMap;
+use std::{env, fs};
+
+use crate::dots::Environment;
+
+/*===========*\
+*  Variables  *
+\*===========*/
+
+/**
+ * Collects the variables available to a dot's templates. Later sources override earlier ones:
+ *
+ * 1. the `[variables]` table in the dot's Dot.toml
+ * 2. the `[variables]` table in the cli's config file, shared by every dot
+ * 3. the dot's table in the local variables file (e.g. `[my_dot]` in `~/.dots/dot-variables.toml`)
+ * 4. environment variables in the form of `DOTS_VAR_<NAME>`
+ */
+pub fn variables(
+    dot_name: &str,
+    defaults: &BTreeMap<String, String>,
+    env: &Environment,
+) -> BTreeMap<String, String> {
+    let mut variables = defaults.clone();
+    variables.extend(env.config().variables.clone());
+
+    let path = env.variables_path();
+    if let Ok(string) = fs::read_to_string(&path) {
+        match toml::from_str::<BTreeMap<String, BTreeMap<String, String>>>(&string) {
+            Ok(mut overrides) => variables.extend(overrides.remove(dot_name).unwrap_or_default()),
+            Err(err) => warn!("Error parsing {path}:\n{err}"),
+        }
+    }
+
+    for (key, value) in env::vars() {
+        if let Some(name) = key.strip_prefix("DOTS_VAR_") {
+            variables.insert(name.to_lowercase(), value);
+        }
+    }
+
+    variables
+}
+
+/*===========*\
+*  Templates  *
+\*===========*/
+
+/// The output of a template that will be written to the generated directory
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RenderedTemplate {
+    /// An absolute path to where the rendered file will be written
+    pub output: Utf8PathBuf,
+    pub contents: String,
+}
+
+/**
+ * Replaces every `{{ name }}` in the template with the value of the matching variable. A `{{` that
+ * is escaped as `\{{` is written out as a literal `{{`.
+ */
+pub fn render(template: &str, variables: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        if let Some(before) = rest[..start].strip_suffix('\\') {
+            output.push_str(before);
+            output.push_str("{{");
+            rest = after;
+            continue;
+        }
+
+        output.push_str(&rest[..start]);
+        let Some(end) = after.find("}}") else {
+            return Err(String::from("Found a \"{{\" without a closing \"}}\""));
+        };
+
+        let name = after[..end].trim();
+        match variables.get(name) {
+            Some(value) => output.push_str(value),
+            None => return Err(format!("Undefined variable \"{name}\"")),
+        }
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    mod render {
+        use crate::template::render;
+        use std::collections::BTreeMap;
+
+        fn variables() -> BTreeMap<String, String> {
+            BTreeMap::from([
+                (String::from("email"), String::from("me@example.com")),
+                (String::from("name"), String::from("Michael")),
+            ])
+        }
+
+        #[test]
+        fn it_should_replace_variables() {
+            let result = render("name = {{name}}\nemail = {{ email }}\n", &variables());
+            assert_eq!(
+                result,
+                Ok(String::from("name = Michael\nemail = me@example.com\n"))
+            );
+        }
+
+        #[test]
+        fn it_should_leave_templates_without_variables_alone() {
+            let result = render("[user]\n", &variables());
+            assert_eq!(result, Ok(String::from("[user]\n")));
+        }
+
+        #[test]
+        fn it_should_fail_on_undefined_variables() {
+            let result = render("{{ editor }}", &variables());
+            assert_eq!(result, Err(String::from("Undefined variable \"editor\"")));
+        }
+
+        #[test]
+        fn it_should_leave_escaped_braces_in_place() {
+            let result = render("PS1='\\{{ .Branch }}' # {{ name }}", &variables());
+            assert_eq!(result, Ok(String::from("PS1='{{ .Branch }}' # Michael")));
+        }
+
+        #[test]
+        fn it_should_fail_on_unclosed_variables() {
+            assert!(render("{{ email", &variables()).is_err());
+        }
+    }
+}
@@ -1,7 +1,9 @@
 mod subcommand_add {
     use std::fs;
-    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
-    use utils::git::commit_all;
+    use test_utils::{
+        cargo_bin, pretty_assert, AssertableOutput, Fixture, TestManager, TestResult,
+    };
+    use utils::git::{commit_all, tag};
 
     const BIN: &str = cargo_bin!("dots");
 
@@ -124,4 +126,186 @@ mod subcommand_add {
 
         Ok(())
     }
+
+    #[test]
+    fn it_should_pin_the_dot_to_the_given_tag() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        tag(&fixture_path, "v1")?;
+        manager.update_fixture(&fixture1, &fixture2)?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--tag")
+            .arg("v1")
+            .output()?;
+
+        output.assert_success();
+
+        assert!(dot_path.join("shell/bashrc").exists());
+        assert!(!dot_path.join("shell/bash_profile").exists());
+        pretty_assert(
+            include_str!("pins/example_dot_with_tag.toml"),
+            fs::read_to_string(manager.dots_dir().join("dot-pins.toml"))?,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_keep_the_pinned_ref_when_overwriting_a_pinned_dot() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        tag(&fixture_path, "v1")?;
+        manager.update_fixture(&fixture1, &fixture2)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--tag")
+            .arg("v1")
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--overwrite")
+            .output()?
+            .assert_success();
+
+        assert!(!dot_path.join("shell/bash_profile").exists());
+        pretty_assert(
+            include_str!("pins/example_dot_with_tag.toml"),
+            fs::read_to_string(manager.dots_dir().join("dot-pins.toml"))?,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_multiple_refs_are_given() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--tag")
+            .arg("v1")
+            .arg("--branch")
+            .arg("main")
+            .output()?
+            .assert_fail_with_code(2);
+
+        assert!(!manager.expected_dot_path(&Fixture::ExampleDot).exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_a_local_directory_in_place_if_it_is_not_a_git_repo() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture(&fixture)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        assert!(!manager.expected_dot_path(&fixture).exists());
+
+        manager
+            .cmd(BIN)?
+            .arg("list")
+            .arg("--origins")
+            .output()?
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
+            .assert_stdout_eq(format!("example_dot => {fixture_path}"))
+            .assert_success();
+
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert_eq!(
+            fs::read_link(home_dir.join(".bashrc"))?,
+            fixture_path.join("shell/bashrc")
+        );
+
+        manager
+            .cmd(BIN)?
+            .arg("uninstall")
+            .arg(fixture.name())
+            .output()?
+            .assert_success();
+
+        assert!(fixture_path.join("Dot.toml").exists());
+        assert!(!home_dir.join(".bashrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_copy_a_local_directory_if_the_copy_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let dot_path = manager.expected_dot_path(&fixture);
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--copy")
+            .output()?
+            .assert_success();
+
+        assert!(dot_path.join("Dot.toml").exists());
+        assert!(!dot_path.join(".git").exists());
+
+        /* the copy can be updated even though it was never cloned */
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_local_dots_that_are_not_git_checkouts_in_the_status() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture(&Fixture::ExampleDot)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("status")
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq(format!(
+                "example_dot\n  not a git checkout ({fixture_path})"
+            ))
+            .assert_success();
+        Ok(())
+    }
 }
@@ -0,0 +1,187 @@
+mod subcommand_adopt {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("adopt").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/adopt_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_move_a_file_into_the_dot_and_link_it_back() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let tmux_path = manager.home_dir().join(".tmux.conf");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(&tmux_path, "set -g mouse on")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("adopt")
+            .arg(&tmux_path)
+            .arg("--into")
+            .arg("example_dot")
+            .output()?
+            .assert_stdout_eq("")
+            .assert_success();
+
+        assert_eq!(tmux_path.read_link_utf8()?, dot_path.join("tmux.conf"));
+        assert_eq!(fs::read_to_string(&tmux_path)?, "set -g mouse on");
+        assert!(manager
+            .read_footprint()?
+            .contains(&format!("dest = \"{tmux_path}\"")));
+
+        /* existing entries are left exactly as they were */
+        assert_eq!(
+            fs::read_to_string(dot_path.join("Dot.toml"))?,
+            "[package]\nname = \"example_dot\"\nauthors = [ \"Michael Mullins\" ]\n\n[link]\n\"~/.bashrc\" = \"shell/bashrc\"\n \"~/.zshrc\" = \"shell/zshrc\"\n\"~/.tmux.conf\" = \"tmux.conf\"\n"
+        );
+
+        /* the adopted link is part of the next install */
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert_eq!(tmux_path.read_link_utf8()?, dot_path.join("tmux.conf"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_adopt_directories_under_a_given_path() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let nvim_path = manager.home_dir().join(".config/nvim");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::create_dir_all(&nvim_path)?;
+        fs::write(nvim_path.join("init.vim"), "set number")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("adopt")
+            .arg(&nvim_path)
+            .arg("--into")
+            .arg("example_dot")
+            .arg("--as")
+            .arg("editor/nvim")
+            .output()?
+            .assert_success();
+
+        assert_eq!(nvim_path.read_link_utf8()?, dot_path.join("editor/nvim"));
+        assert_eq!(
+            fs::read_to_string(dot_path.join("editor/nvim/init.vim"))?,
+            "set number"
+        );
+        assert!(fs::read_to_string(dot_path.join("Dot.toml"))?
+            .contains("\"~/.config/nvim\" = \"editor/nvim\"\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_leave_the_file_alone_if_the_dot_already_has_that_path() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let profile_path = manager.home_dir().join(".profile");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(&profile_path, "export EDITOR=vim")?;
+        let dot_toml = fs::read_to_string(dot_path.join("Dot.toml"))?;
+
+        manager
+            .cmd(BIN)?
+            .arg("adopt")
+            .arg(&profile_path)
+            .arg("--into")
+            .arg("example_dot")
+            .arg("--as")
+            .arg("shell/bashrc")
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert!(!profile_path.is_symlink());
+        assert_eq!(fs::read_to_string(&profile_path)?, "export EDITOR=vim");
+        assert_eq!(fs::read_to_string(dot_path.join("Dot.toml"))?, dot_toml);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_put_back_an_adoption_that_was_interrupted() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let tmux_path = manager.home_dir().join(".tmux.conf");
+        let adopted_path = dot_path.join("tmux.conf");
+        let package_path = dot_path.join("Dot.toml");
+        let journal_path = manager.dots_dir().join("dot-journal.toml");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        /* Leave things as they'd be if dots was killed while updating the Dot.toml */
+        let package = fs::read_to_string(&package_path)?;
+        fs::write(&adopted_path, "set -g mouse on")?;
+        fs::write(&package_path, "")?;
+        fs::write(
+            &journal_path,
+            format!(
+                r#"timestamp = "2024-01-01T00:00:00Z"
+
+[[steps]]
+step = "moved"
+from = "{tmux_path}"
+to = "{adopted_path}"
+
+[[steps]]
+step = "wrote_file"
+path = "{package_path}"
+previous = """{package}"""
+"#
+            ),
+        )?;
+
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        assert!(!tmux_path.is_symlink());
+        assert_eq!(fs::read_to_string(&tmux_path)?, "set -g mouse on");
+        assert!(!adopted_path.exists());
+        assert_eq!(fs::read_to_string(&package_path)?, package);
+        assert!(!journal_path.exists());
+        Ok(())
+    }
+}
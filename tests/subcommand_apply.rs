@@ -0,0 +1,226 @@
+mod subcommand_apply {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("apply").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/apply_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_link_everything_in_a_saved_plan() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let plan_path = home_dir.join("plan.json");
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--save-plan")
+            .arg(&plan_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert!(plan_path.exists());
+        assert!(!home_dir.join(".bashrc").exists());
+
+        manager
+            .cmd(BIN)?
+            .arg("apply")
+            .arg(&plan_path)
+            .output()?
+            .assert_stderr_eq("[info] Install was a success!\n")
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(home_dir.join(".zshrc").is_symlink());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_back_up_files_if_the_plan_was_forced() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let plan_path = home_dir.join("plan.json");
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+        fs::write(home_dir.join(".zshrc"), "original zshrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--force")
+            .arg("--save-plan")
+            .arg(&plan_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        manager
+            .cmd(BIN)?
+            .arg("apply")
+            .arg(&plan_path)
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".zshrc").is_symlink());
+        assert!(manager.dots_dir().join(".backups").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_refuse_to_apply_a_plan_if_a_destination_has_changed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let plan_path = home_dir.join("plan.json");
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--save-plan")
+            .arg(&plan_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        fs::write(home_dir.join(".bashrc"), "new bashrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("apply")
+            .arg(&plan_path)
+            .output()?
+            .assert_stderr_eq(format!(
+                include_str!("output/apply_fail_with_changed_destination.err"),
+                HOME = &home_dir
+            ))
+            .assert_fail_with_code(1);
+
+        assert_eq!(fs::read_to_string(home_dir.join(".bashrc"))?, "new bashrc");
+        assert!(!home_dir.join(".zshrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_not_save_a_plan_that_fails_validation() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let plan_path = home_dir.join("plan.json");
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+        fs::write(home_dir.join(".zshrc"), "original zshrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--save-plan")
+            .arg(&plan_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert!(!plan_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_leave_other_dots_alone_when_applying_a_plan_for_a_single_dot() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let other_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithDirectory)?;
+        let home_dir = manager.home_dir();
+        let plan_path = home_dir.join("plan.json");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&other_path)
+            .output()?
+            .assert_success();
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(Fixture::ExampleDot.name())
+            .arg("--dry")
+            .arg("--save-plan")
+            .arg(&plan_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        manager
+            .cmd(BIN)?
+            .arg("apply")
+            .arg(&plan_path)
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(home_dir.join("bin").is_symlink());
+        assert!(manager.read_footprint()?.contains("/bin\""));
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_apply_a_plan_that_replaces_a_stale_link() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithMultiLink)?;
+        let home_dir = manager.home_dir();
+        let plan_path = home_dir.join("plan.json");
+        let bash_profile_path = home_dir.join(".bash_profile");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        manager.overwrite_dot(
+            &Fixture::ExampleDotWithMultiLink,
+            &Fixture::ExampleDotWithLinkAdded,
+        )?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--save-plan")
+            .arg(&plan_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        manager
+            .cmd(BIN)?
+            .arg("apply")
+            .arg(&plan_path)
+            .output()?
+            .assert_success();
+
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        assert_eq!(
+            fs::read_link(&bash_profile_path)?,
+            dot_path.join("shell/bash_profile")
+        );
+        Ok(())
+    }
+}
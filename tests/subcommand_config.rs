@@ -0,0 +1,180 @@
+mod subcommand_config {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("config").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/config_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_set_get_and_list_keys() -> TestResult {
+        let manager = TestManager::new()?;
+
+        for (key, value) in [
+            ("install.force", "true"),
+            ("variables.email", "me@example.com"),
+        ] {
+            manager
+                .cmd(BIN)?
+                .args(["config", "set", key, value])
+                .output()?
+                .assert_stderr_eq("")
+                .assert_success();
+        }
+
+        manager
+            .cmd(BIN)?
+            .args(["config", "get", "variables.email"])
+            .output()?
+            .assert_stdout_eq("me@example.com")
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .args(["config", "list"])
+            .output()?
+            .assert_stdout_eq("install.force = true\nvariables.email = me@example.com")
+            .assert_success();
+
+        assert_eq!(
+            fs::read_to_string(manager.config_path())?,
+            "[install]\nforce = true\n\n[variables]\nemail = \"me@example.com\"\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_to_get_a_key_that_isnt_set() -> TestResult {
+        let manager = TestManager::new()?;
+
+        manager
+            .cmd(BIN)?
+            .args(["config", "get", "install.force"])
+            .output()?
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_refuse_to_set_unknown_keys() -> TestResult {
+        let manager = TestManager::new()?;
+
+        manager
+            .cmd(BIN)?
+            .args(["config", "set", "install.forse", "true"])
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert!(!manager.config_path().exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_refuse_to_run_with_a_config_that_cant_be_parsed() -> TestResult {
+        let manager = TestManager::new()?;
+        let config_path = manager.config_path();
+
+        fs::create_dir_all(config_path.parent().unwrap())?;
+        fs::write(&config_path, "roto = \"~/dotfiles\"\n")?;
+
+        let output = manager.cmd(BIN)?.arg("list").output()?;
+        output.assert_fail_with_code(1);
+        assert!(String::from_utf8(output.stderr)?
+            .starts_with(&format!("[error] Unable to parse {config_path}:")));
+
+        /* the config can still be read with `dots config` to fix it */
+        manager
+            .cmd(BIN)?
+            .args(["config", "list"])
+            .output()?
+            .assert_stdout_eq("roto = ~/dotfiles")
+            .assert_success();
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_the_configured_defaults_when_installing() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let config_path = manager.config_path();
+
+        fs::create_dir_all(config_path.parent().unwrap())?;
+        fs::write(
+            &config_path,
+            format!("[install]\nforce = true\n\n[registry]\nexample = \"{fixture_path}\"\n"),
+        )?;
+        fs::write(home_dir.join(".bashrc"), "original bashrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("example")
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(manager.dots_dir().join(".backups").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_let_no_force_override_the_configured_force() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let config_path = manager.config_path();
+
+        fs::create_dir_all(config_path.parent().unwrap())?;
+        fs::write(&config_path, "[install]\nforce = true\n")?;
+        fs::write(home_dir.join(".bashrc"), "original bashrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--no-force")
+            .arg(&fixture_path)
+            .output()?
+            .assert_fail();
+
+        assert_eq!(
+            fs::read_to_string(home_dir.join(".bashrc"))?,
+            "original bashrc"
+        );
+        assert!(!manager.dots_dir().join(".backups").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_the_configured_variables_in_templates() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithTemplate)?;
+        let config_path = manager.config_path();
+
+        fs::create_dir_all(config_path.parent().unwrap())?;
+        fs::write(&config_path, "[variables]\nname = \"Config Name\"\n")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        let gitconfig = fs::read_to_string(manager.home_dir().join(".gitconfig"))?;
+        assert!(gitconfig.contains("Config Name"));
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+mod subcommand_doctor {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("doctor").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/doctor_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_find_nothing_wrong_with_a_fresh_install() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("doctor")
+            .output()?
+            .assert_stdout_eq("")
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_and_fix_broken_links() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::remove_file(home_dir.join(".bashrc"))?;
+        fs::remove_file(dot_path.join("shell/zshrc"))?;
+
+        manager
+            .cmd(BIN)?
+            .arg("doctor")
+            .output()?
+            .assert_stdout_eq(format!(
+                "- {home_dir}/.bashrc is tracked, but it isn't linked to {dot_path}/shell/bashrc\n- {home_dir}/.zshrc points to {dot_path}/shell/zshrc, which doesn't exist"
+            ))
+            .assert_fail_with_code(1);
+
+        manager
+            .cmd(BIN)?
+            .arg("doctor")
+            .arg("--fix")
+            .output()?
+            .assert_stdout_eq(format!(
+                "- {home_dir}/.bashrc is tracked, but it isn't linked to {dot_path}/shell/bashrc (fixed)\n- {home_dir}/.zshrc points to {dot_path}/shell/zshrc, which doesn't exist (fixed)"
+            ))
+            .assert_success();
+
+        assert!(!home_dir.join(".zshrc").is_symlink());
+        assert!(!manager.read_footprint()?.contains(".zshrc"));
+        manager.cmd(BIN)?.arg("doctor").output()?.assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_track_links_missing_from_the_footprint() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager.write_footprint("links = [")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("doctor")
+            .arg("--fix")
+            .output()?
+            .assert_success();
+
+        let footprint = manager.read_footprint()?;
+        assert!(footprint.contains(&format!("dest = \"{home_dir}/.bashrc\"")));
+        assert!(footprint.contains(&format!("dest = \"{home_dir}/.zshrc\"")));
+        let backups: Vec<_> = fs::read_dir(manager.dots_dir())?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                let name = path.file_name().unwrap().to_string_lossy();
+                name.starts_with("dot-footprint.toml.") && name.ends_with(".bak")
+            })
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0])?, "links = [");
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_broken_dots_without_touching_their_links() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(dot_path.join("Dot.toml"), "[package]\nname = ")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("--format")
+            .arg("json")
+            .arg("doctor")
+            .arg("--fix")
+            .output()?
+            .assert_stdout_eq(format!(
+                "{{\n  \"version\": 1,\n  \"problems\": [\n    {{\n      \"kind\": \"broken_dot\",\n      \"message\": \"{dot_path}: Dot.toml:2:8: unexpected eof encountered\",\n      \"fixable\": false,\n      \"fixed\": false\n    }}\n  ]\n}}\n"
+            ))
+            .assert_fail_with_code(1);
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(manager.read_footprint()?.contains(".bashrc"));
+
+        Ok(())
+    }
+}
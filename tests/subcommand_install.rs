@@ -1,9 +1,15 @@
 mod subcommand_install {
-    use std::{fs, os::unix};
+    use std::{
+        fs,
+        os::unix::{self, fs::MetadataExt},
+    };
     use test_utils::{
         cargo_bin, pretty_assert, AssertableOutput, Fixture, TestManager, TestResult,
     };
-    use utils::{fs::soft_link, git::commit_all};
+    use utils::{
+        fs::soft_link,
+        git::{self, commit_all},
+    };
 
     const BIN: &str = cargo_bin!("dots");
 
@@ -243,6 +249,107 @@ mod subcommand_install {
         Ok(())
     }
 
+    #[test]
+    fn it_should_only_link_the_dots_that_are_named() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithDirectory;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let fixture2_path = manager.setup_fixture_as_git_repo(&fixture2)?;
+        let home_dir = manager.home_dir();
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture1_path).output()?;
+        manager.cmd(BIN)?.arg("add").arg(&fixture2_path).output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(fixture1.name())
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(home_dir.join(".zshrc").is_symlink());
+        assert!(!home_dir.join("bin").exists());
+
+        let footprint = manager.read_footprint()?;
+        assert!(footprint.contains(r#"dot = "example_dot""#));
+        assert!(!footprint.contains("/bin"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_leave_the_links_of_dots_that_are_not_named_alone() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithDirectory;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let fixture2_path = manager.setup_fixture_as_git_repo(&fixture2)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture1_path)
+            .output()?;
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture2_path)
+            .output()?;
+        std::fs::remove_file(home_dir.join("bin"))?;
+        std::fs::remove_file(home_dir.join(".zshrc"))?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(fixture1.name())
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".zshrc").is_symlink());
+        assert!(!home_dir.join("bin").exists());
+        assert!(manager.read_footprint()?.contains("/bin"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_a_named_dot_links_the_same_thing_as_another_dot() -> TestResult {
+        let manager = TestManager::new()?;
+        let main_fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let conflicting_fixture_path =
+            manager.setup_fixture_as_git_repo(&Fixture::ConflictingDot)?;
+        let home_dir = manager.home_dir();
+        let dot_dir = manager.expected_dot_path(&Fixture::ExampleDot);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&main_fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&conflicting_fixture_path)
+            .output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--force")
+            .arg(Fixture::ConflictingDot.name())
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert_eq!(
+            home_dir.join(".bashrc").read_link()?,
+            dot_dir.join("shell/bashrc")
+        );
+        Ok(())
+    }
+
     #[test]
     fn it_should_succeed_if_you_try_to_overwrite_a_symlink_to_the_same_file() -> TestResult {
         let manager = TestManager::new()?;
@@ -394,6 +501,61 @@ mod subcommand_install {
         Ok(())
     }
 
+    #[test]
+    fn it_should_show_how_an_existing_file_differs_from_the_dotfile() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+
+        let bashrc_path = home_dir.join(".bashrc");
+        fs::write(&bashrc_path, "#bashrc old\nexport EDITOR=nano\n")?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--diff")
+            .output()?;
+        let expected_err = std::include_str!("output/install_dry_with_diff.err");
+
+        output
+            .assert_stderr_eq(expected_err)
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        assert!(bashrc_path.is_file());
+        assert!(!bashrc_path.is_symlink());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_summarize_how_an_existing_directory_differs_from_the_dotfile() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithDirectory)?;
+        let home_dir = manager.home_dir();
+
+        fs::create_dir(home_dir.join("bin"))?;
+        fs::write(home_dir.join("bin/old"), "hi\n")?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--diff")
+            .output()?;
+        let expected_err = std::include_str!("output/install_dry_with_directory_diff.err");
+
+        output
+            .assert_stderr_eq(expected_err)
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+        Ok(())
+    }
+
     #[test]
     fn it_should_succeed_if_you_explicitely_force_an_overwrite_of_an_existing_file() -> TestResult {
         let manager = TestManager::new()?;
@@ -408,7 +570,10 @@ mod subcommand_install {
         manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
 
         let output = manager.cmd(BIN)?.arg("install").arg("--force").output()?;
-        let expected_err = std::include_str!("output/install_success.err");
+        let expected_err = format!(
+            include_str!("output/install_success_with_backup.err"),
+            HOME = home_dir
+        );
 
         output
             .assert_stderr_eq(expected_err)
@@ -428,6 +593,35 @@ mod subcommand_install {
         Ok(())
     }
 
+    #[test]
+    fn it_should_back_up_an_existing_file_when_forcing_an_overwrite() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let bashrc_path = home_dir.join(".bashrc");
+
+        fs::write(&bashrc_path, "original bashrc")?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--force")
+            .output()?
+            .assert_success();
+
+        let backups: Vec<_> = fs::read_dir(manager.dots_dir().join(".backups"))?.collect();
+        assert_eq!(backups.len(), 1);
+
+        let backup_path = backups[0].as_ref().unwrap().path().join(".bashrc");
+        assert_eq!(fs::read_to_string(&backup_path)?, "original bashrc");
+
+        let footprint = manager.read_footprint()?;
+        assert!(footprint.contains("[[backups]]"));
+        assert!(footprint.contains(&format!("path = \"{bashrc_path}\"")));
+        Ok(())
+    }
+
     #[test]
     fn it_should_fail_if_you_try_to_overwrite_a_dir_and_its_contents() -> TestResult {
         let manager = TestManager::new()?;
@@ -465,7 +659,10 @@ mod subcommand_install {
         manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
 
         let output = manager.cmd(BIN)?.arg("install").arg("--force").output()?;
-        let expected_err = std::include_str!("output/install_success.err");
+        let expected_err = format!(
+            include_str!("output/install_success_with_backup.err"),
+            HOME = home_dir
+        );
 
         output
             .assert_stderr_eq(expected_err)
@@ -483,6 +680,74 @@ mod subcommand_install {
         Ok(())
     }
 
+    #[test]
+    fn it_should_roll_back_all_changes_if_the_install_fails_part_way_through() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithNestedLink)?;
+        let home_dir = manager.home_dir();
+        let footprint_path = manager.footprint_path();
+
+        let bashrc_path = home_dir.join(".bashrc");
+        let config_path = home_dir.join(".config");
+
+        fs::write(&bashrc_path, "original bashrc")?;
+        // a file in place of the ~/.config directory will cause the second link to fail
+        fs::write(&config_path, "not a directory")?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        let output = manager.cmd(BIN)?.arg("install").arg("--force").output()?;
+        let expected_err = format!(
+            include_str!("output/install_fail_and_rollback.err"),
+            HOME = home_dir
+        );
+
+        output
+            .assert_stderr_eq(expected_err)
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        assert!(!bashrc_path.is_symlink());
+        assert_eq!(fs::read_to_string(&bashrc_path)?, "original bashrc");
+        assert_eq!(fs::read_to_string(&config_path)?, "not a directory");
+        assert!(!footprint_path.exists());
+        assert!(!manager.dots_dir().join(".backups").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_restore_the_previous_footprint_if_the_install_fails_part_way_through() -> TestResult
+    {
+        let manager = TestManager::new()?;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithDirectory)?;
+        let fixture2_path =
+            manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithNestedLink)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture1_path)
+            .output()?
+            .assert_success();
+
+        let footprint = manager.read_footprint()?;
+
+        fs::write(home_dir.join(".config"), "not a directory")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture2_path)
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert!(!home_dir.join(".bashrc").exists());
+        assert!(home_dir.join("bin").is_symlink());
+        pretty_assert(footprint, manager.read_footprint()?);
+        Ok(())
+    }
+
     #[test]
     fn it_should_write_the_installed_links_to_a_dot_footprint() -> TestResult {
         let manager = TestManager::new()?;
@@ -714,4 +979,874 @@ mod subcommand_install {
 
         Ok(())
     }
+
+    #[test]
+    pub fn it_should_leave_the_links_of_dots_with_a_broken_dot_toml_alone() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let home_path = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        let footprint = manager.read_footprint()?;
+
+        let dot_toml_path = manager.expected_dot_path(&fixture).join("Dot.toml");
+        fs::write(
+            &dot_toml_path,
+            "[package]\nname = \"example_dot\"\nauthors = [\n",
+        )?;
+
+        let output = manager.cmd(BIN)?.arg("install").output()?;
+        output.assert_success();
+        assert!(String::from_utf8(output.stderr)?
+            .contains("✖ Dot.toml:4:1: unexpected eof encountered"));
+
+        assert!(home_path.join(".bashrc").is_symlink());
+        assert!(home_path.join(".zshrc").is_symlink());
+        pretty_assert(footprint, manager.read_footprint()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_check_out_the_locked_commit_of_each_dot_when_locked_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&fixture1);
+        let locked_commit = git::rev_parse(&fixture_path, "HEAD")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+        assert!(home_dir.join(".bash_profile").is_symlink());
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--locked")
+            .output()?
+            .assert_success();
+
+        assert_eq!(git::rev_parse(&dot_path, "HEAD")?, locked_commit);
+        assert!(!home_dir.join(".bash_profile").exists());
+        assert!(home_dir.join(".bashrc").is_symlink());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_add_missing_dots_from_the_lockfile_when_locked_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&fixture1);
+        let locked_commit = git::rev_parse(&fixture_path, "HEAD")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+        manager.remove_dot(&fixture1)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--locked")
+            .output()?
+            .assert_success();
+
+        assert_eq!(git::rev_parse(&dot_path, "HEAD")?, locked_commit);
+        assert!(!dot_path.join("shell/bash_profile").exists());
+        assert!(home_dir.join(".bashrc").is_symlink());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_not_check_anything_out_when_locked_is_passed_with_dry() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let dot_path = manager.expected_dot_path(&fixture1);
+        let locked_commit = git::rev_parse(&fixture_path, "HEAD")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+        let updated_commit = git::rev_parse(&dot_path, "HEAD")?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--locked")
+            .arg("--dry")
+            .output()?;
+
+        output.assert_fail_with_code(1);
+        assert!(String::from_utf8(output.stderr)?
+            .contains(&format!("Would check out example_dot at {locked_commit}")));
+        assert_eq!(git::rev_parse(&dot_path, "HEAD")?, updated_commit);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_locked_is_passed_without_a_lockfile() -> TestResult {
+        let manager = TestManager::new()?;
+
+        let output = manager.cmd(BIN)?.arg("install").arg("--locked").output()?;
+
+        output.assert_stdout_eq("").assert_fail_with_code(1);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_skip_links_whose_conditions_do_not_match_the_current_machine() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path =
+            manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithConditionalLinks)?;
+        let home_dir = manager.home_dir();
+
+        let output = manager
+            .cmd(BIN)?
+            .env("DOTS_HOSTNAME", "build-box")
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?;
+
+        output
+            .assert_stderr_eq(format!(
+                include_str!("output/install_success_with_skipped_links.err"),
+                SRC_PATH = fixture_path,
+                DEST_PATH = manager.expected_dot_path(&Fixture::ExampleDot),
+            ))
+            .assert_stdout_eq("")
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(home_dir.join(".zshrc").is_symlink());
+        assert!(!home_dir.join(".bash_profile").exists());
+        assert!(!home_dir.join(".profile").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_install_host_links_on_the_matching_host() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path =
+            manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithConditionalLinks)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .env("DOTS_HOSTNAME", "workstation")
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".profile").is_symlink());
+        assert!(!home_dir.join(".bash_profile").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_only_install_the_links_in_the_selected_profile() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithProfiles)?;
+        let home_dir = manager.home_dir();
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .arg("--profile")
+            .arg("minimal")
+            .output()?;
+
+        output
+            .assert_stderr_eq(format!(
+                include_str!("output/install_success_with_profile.err"),
+                SRC_PATH = fixture_path,
+                DEST_PATH = manager.expected_dot_path(&Fixture::ExampleDot),
+            ))
+            .assert_stdout_eq("")
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(!home_dir.join(".zshrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_remember_the_selected_profile() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithProfiles)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .arg("--profile")
+            .arg("minimal")
+            .output()?
+            .assert_success();
+
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert!(!home_dir.join(".zshrc").exists());
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--no-profile")
+            .output()?
+            .assert_success();
+        assert!(home_dir.join(".zshrc").is_symlink());
+
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert!(home_dir.join(".zshrc").is_symlink());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_relink_a_destination_whose_source_changed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let dot_path = manager.expected_dot_path(&fixture);
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        let dot_toml_path = dot_path.join("Dot.toml");
+        let dot_toml = fs::read_to_string(&dot_toml_path)?;
+        fs::write(
+            &dot_toml_path,
+            dot_toml.replace("\"shell/bashrc\"", "\"shell/zshrc\""),
+        )?;
+
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+
+        assert_eq!(
+            fs::read_link(home_dir.join(".bashrc"))?,
+            dot_path.join("shell/zshrc")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_not_remember_a_profile_that_no_dot_defines() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithProfiles)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .arg("--profile")
+            .arg("minimal")
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--profile")
+            .arg("typo")
+            .output()?
+            .assert_success();
+        assert!(manager.home_dir().join(".zshrc").is_symlink());
+
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert!(!manager.home_dir().join(".zshrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_remove_links_outside_of_a_newly_selected_profile() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithProfiles)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        assert!(home_dir.join(".zshrc").is_symlink());
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--profile")
+            .arg("minimal")
+            .output()?
+            .assert_success();
+
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(!home_dir.join(".zshrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_link_templates_to_their_rendered_output() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithTemplate)?;
+        let home_dir = manager.home_dir();
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?;
+
+        output
+            .assert_stderr_eq(format!(
+                include_str!("output/install_success_with_template.err"),
+                SRC_PATH = fixture_path,
+                DEST_PATH = manager.expected_dot_path(&Fixture::ExampleDot),
+            ))
+            .assert_stdout_eq("")
+            .assert_success();
+
+        let rendered = manager
+            .dots_dir()
+            .join(".generated/example_dot/git/gitconfig");
+        assert_eq!(home_dir.join(".gitconfig").read_link()?, rendered);
+        assert_eq!(
+            fs::read_to_string(&rendered)?,
+            "[user]\n\tname = Michael Mullins\n\temail = michael@example.com\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_prefer_local_and_environment_variables_when_rendering() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithTemplate)?;
+        let home_dir = manager.home_dir();
+
+        fs::create_dir_all(manager.dots_dir())?;
+        fs::write(
+            manager.dots_dir().join("dot-variables.toml"),
+            "[example_dot]\nname = \"Local Name\"\nemail = \"local@example.com\"\n",
+        )?;
+
+        manager
+            .cmd(BIN)?
+            .env("DOTS_VAR_EMAIL", "env@example.com")
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        assert_eq!(
+            fs::read_to_string(home_dir.join(".gitconfig"))?,
+            "[user]\n\tname = Local Name\n\temail = env@example.com\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_remove_stale_template_output() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithTemplate)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager.overwrite_dot(&Fixture::ExampleDotWithTemplate, &Fixture::ExampleDot)?;
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+
+        assert!(!home_dir.join(".gitconfig").exists());
+        assert!(!manager.dots_dir().join(".generated").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_put_back_stale_template_output_if_the_install_fails() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithTemplate)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        let footprint = manager.read_footprint()?;
+        let gitconfig = fs::read_to_string(home_dir.join(".gitconfig"))?;
+
+        manager.overwrite_dot(
+            &Fixture::ExampleDotWithTemplate,
+            &Fixture::ExampleDotWithNestedLink,
+        )?;
+        fs::write(home_dir.join(".config"), "not a directory")?;
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--force")
+            .output()?
+            .assert_fail_with_code(1);
+
+        assert_eq!(fs::read_to_string(home_dir.join(".gitconfig"))?, gitconfig);
+        pretty_assert(footprint, manager.read_footprint()?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_leave_stale_template_output_alone_on_a_dry_run() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithTemplate)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        let footprint = manager.read_footprint()?;
+
+        manager.overwrite_dot(&Fixture::ExampleDotWithTemplate, &Fixture::ExampleDot)?;
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .output()?
+            .assert_fail();
+
+        assert!(home_dir.join(".gitconfig").exists());
+        pretty_assert(footprint, manager.read_footprint()?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_copy_links_with_the_copy_mode() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithCopy)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        let zshrc_path = home_dir.join(".zshrc");
+        assert!(home_dir.join(".bashrc").is_symlink());
+        assert!(zshrc_path.is_file() && !zshrc_path.is_symlink());
+        assert_eq!(
+            fs::read_to_string(&zshrc_path)?,
+            fs::read_to_string(dot_path.join("shell/zshrc"))?
+        );
+        assert!(manager.read_footprint()?.contains("mode = \"copy\""));
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_update_untouched_copies_when_their_source_changes() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithCopy)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(dot_path.join("shell/zshrc"), "updated zshrc")?;
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+
+        assert_eq!(
+            fs::read_to_string(home_dir.join(".zshrc"))?,
+            "updated zshrc"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_warn_when_a_copy_was_changed_since_it_was_copied() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithCopy)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(home_dir.join(".zshrc"), "local changes")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .output()?
+            .assert_stderr_eq(include_str!("output/install_fail_with_modified_copy.err"))
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        assert_eq!(
+            fs::read_to_string(home_dir.join(".zshrc"))?,
+            "local changes"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_hard_link_links_with_the_hardlink_mode() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithHardlink)?;
+        let zshrc_path = manager.home_dir().join(".zshrc");
+        let src_path = manager
+            .expected_dot_path(&Fixture::ExampleDot)
+            .join("shell/zshrc");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        assert!(!zshrc_path.is_symlink());
+        assert_eq!(
+            fs::metadata(&zshrc_path)?.ino(),
+            fs::metadata(&src_path)?.ino()
+        );
+        assert!(manager.read_footprint()?.contains("mode = \"hardlink\""));
+
+        /* installing again should leave the hard link in place */
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert_eq!(
+            fs::metadata(&zshrc_path)?.ino(),
+            fs::metadata(&src_path)?.ino()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_relink_hardlinks_when_their_source_is_replaced() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithHardlink)?;
+        let zshrc_path = manager.home_dir().join(".zshrc");
+        let src_path = manager
+            .expected_dot_path(&Fixture::ExampleDot)
+            .join("shell/zshrc");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        /* replace the source with a new file, the same way git does when checking out */
+        let replacement = src_path.with_file_name("zshrc.new");
+        fs::write(&replacement, fs::read(&src_path)?)?;
+        fs::rename(&replacement, &src_path)?;
+
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        assert_eq!(
+            fs::metadata(&zshrc_path)?.ino(),
+            fs::metadata(&src_path)?.ino()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_replace_a_hardlink_when_its_mode_changes() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithHardlink)?;
+        let zshrc_path = manager.home_dir().join(".zshrc");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager.overwrite_dot(&Fixture::ExampleDotWithHardlink, &Fixture::ExampleDot)?;
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+
+        assert!(zshrc_path.is_symlink());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_print_the_install_plan_as_json_if_the_json_format_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let home_dir = manager.home_dir();
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+        fs::write(home_dir.join(".zshrc"), "")?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--dry")
+            .arg("--format")
+            .arg("json")
+            .output()?;
+
+        output
+            .assert_stdout_eq(format!(
+                include_str!("output/install_fail_with_json_format.out"),
+                HOME = &home_dir,
+                DOT_PATH = manager.expected_dot_path(&fixture),
+            ))
+            .assert_fail_with_code(1);
+
+        assert!(!home_dir.join(".bashrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_install_to_the_given_root_and_footprint() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let home_dir = manager.home_dir();
+        let root = home_dir.join("custom_root");
+        let footprint_path = home_dir.join("state/footprint.toml");
+
+        manager
+            .cmd(BIN)?
+            .env("DOTS_FOOTPRINT", &footprint_path)
+            .arg("install")
+            .arg(&fixture_path)
+            .arg("--root")
+            .arg(&root)
+            .output()?
+            .assert_success();
+
+        assert!(!manager.dots_dir().exists());
+        assert!(root.join(fixture.name()).join("Dot.toml").exists());
+        assert!(footprint_path.exists());
+        assert!(!root.join("dot-footprint.toml").exists());
+        assert_eq!(
+            fs::read_link(home_dir.join(".bashrc"))?,
+            root.join(fixture.name()).join("shell/bashrc")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_roll_back_an_install_that_was_interrupted() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let home_dir = manager.home_dir();
+        let bashrc_path = home_dir.join(".bashrc");
+        let moved_path = home_dir.join(".bashrc.2024-01-01T00:00:00Z.dots-removed");
+        let journal_path = manager.dots_dir().join("dot-journal.toml");
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        /* Leave things as they'd be if dots was killed after overwriting ~/.bashrc */
+        let src_path = manager.expected_dot_path(&fixture).join("shell/bashrc");
+        fs::write(&moved_path, "original bashrc")?;
+        soft_link(&bashrc_path, &src_path)?;
+        fs::write(
+            &journal_path,
+            format!(
+                r#"timestamp = "2024-01-01T00:00:00Z"
+
+[[steps]]
+step = "removed"
+path = "{bashrc_path}"
+moved_to = "{moved_path}"
+
+[[steps]]
+step = "created_symlink"
+
+[steps.link]
+src = "{src_path}"
+dest = "{bashrc_path}"
+mode = "symlink"
+"#
+            ),
+        )?;
+
+        let output = manager.cmd(BIN)?.arg("install").output()?;
+        output.assert_fail();
+        assert!(String::from_utf8(output.stderr)?
+            .starts_with("[warn] The last install didn't finish, rolling back its changes..."));
+
+        assert!(!bashrc_path.is_symlink());
+        assert_eq!(fs::read_to_string(&bashrc_path)?, "original bashrc");
+        assert!(!moved_path.exists());
+        assert!(!journal_path.exists());
+        assert!(!manager.footprint_path().exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_roll_back_steps_that_were_recorded_but_never_made() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let home_dir = manager.home_dir();
+        let bashrc_path = home_dir.join(".bashrc");
+        let config_path = home_dir.join(".config");
+        let journal_path = manager.dots_dir().join("dot-journal.toml");
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        /* Leave things as they'd be if dots was killed right after writing the journal */
+        let src_path = manager.expected_dot_path(&fixture).join("shell/bashrc");
+        fs::write(&bashrc_path, "original bashrc")?;
+        fs::write(
+            &journal_path,
+            format!(
+                r#"timestamp = "2024-01-01T00:00:00Z"
+
+[[steps]]
+step = "created_dir"
+path = "{config_path}"
+
+[[steps]]
+step = "removed"
+path = "{bashrc_path}"
+moved_to = "{home_dir}/.bashrc.2024-01-01T00:00:00Z.dots-removed"
+
+[[steps]]
+step = "created_symlink"
+
+[steps.link]
+src = "{src_path}"
+dest = "{bashrc_path}"
+mode = "symlink"
+"#
+            ),
+        )?;
+
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        assert_eq!(fs::read_to_string(&bashrc_path)?, "original bashrc");
+        assert!(!config_path.exists());
+        assert!(!journal_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_keep_the_journal_when_the_rollback_fails() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let config_path = manager.home_dir().join(".config");
+        let journal_path = manager.dots_dir().join("dot-journal.toml");
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        /* The directory was created by the install, but something else was put in it since */
+        fs::create_dir_all(config_path.join("other"))?;
+        fs::write(
+            &journal_path,
+            format!(
+                r#"timestamp = "2024-01-01T00:00:00Z"
+
+[[steps]]
+step = "created_dir"
+path = "{config_path}"
+"#
+            ),
+        )?;
+
+        let output = manager.cmd(BIN)?.arg("install").output()?;
+        output.assert_fail_with_code(1);
+        assert!(String::from_utf8(output.stderr)?
+            .contains(&format!("Unable to undo created directory {config_path}")));
+
+        assert!(config_path.join("other").exists());
+        assert!(fs::read_to_string(&journal_path)?.contains(config_path.as_str()));
+        assert!(!manager.home_dir().join(".bashrc").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_refuse_to_install_over_a_footprint_that_cant_be_parsed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let footprint_path = manager.footprint_path();
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+        manager.write_footprint("links = [")?;
+
+        let output = manager.cmd(BIN)?.arg("install").output()?;
+        output.assert_fail_with_code(1);
+        assert!(String::from_utf8(output.stderr)?.contains(&format!(
+            "{footprint_path} can't be parsed, run `dots doctor --fix` to move it out of the way"
+        )));
+
+        assert!(!manager.home_dir().join(".bashrc").exists());
+        assert_eq!(fs::read_to_string(&footprint_path)?, "links = [");
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
 mod subcommand_list {
+    use std::fs;
     use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
 
     const BIN: &str = cargo_bin!("dots");
@@ -22,7 +23,10 @@ mod subcommand_list {
         let output = manager.cmd(BIN)?.arg("list").output()?;
 
         output
-            .assert_stderr_eq("")
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
             .assert_stdout_eq("")
             .assert_success();
 
@@ -43,7 +47,10 @@ mod subcommand_list {
         let output = manager.cmd(BIN)?.arg("list").output()?;
 
         output
-            .assert_stderr_eq("")
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
             .assert_stdout_eq(include_str!(
                 "output/list_success_with_multiple_directories.out"
             ))
@@ -52,6 +59,37 @@ mod subcommand_list {
         Ok(())
     }
 
+    #[test]
+    fn it_should_list_dots_whose_dot_toml_is_broken() -> TestResult {
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithDirectory;
+        let manager = TestManager::new()?;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let fixture2_path = manager.setup_fixture_as_git_repo(&fixture2)?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture1_path).output()?;
+        manager.cmd(BIN)?.arg("add").arg(&fixture2_path).output()?;
+
+        let dot_toml_path = manager.expected_dot_path(&fixture1).join("Dot.toml");
+        fs::write(
+            &dot_toml_path,
+            "[package]\nname = \"example_dot\"\nauthors = [\n",
+        )?;
+
+        manager
+            .cmd(BIN)?
+            .arg("list")
+            .output()?
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
+            .assert_stdout_eq("example_dot_with_directory\nexample_dot (broken, Dot.toml:4:1: unexpected eof encountered)")
+            .assert_success();
+
+        Ok(())
+    }
+
     #[test]
     fn it_should_also_work_with_the_ls_shorthand_alias() -> TestResult {
         let fixture1 = Fixture::ExampleDot;
@@ -66,7 +104,10 @@ mod subcommand_list {
         let output = manager.cmd(BIN)?.arg("ls").output()?;
 
         output
-            .assert_stderr_eq("")
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
             .assert_stdout_eq(include_str!(
                 "output/list_success_with_multiple_directories.out"
             ))
@@ -89,7 +130,10 @@ mod subcommand_list {
         let output = manager.cmd(BIN)?.arg("list").arg("--origins").output()?;
 
         output
-            .assert_stderr_eq("")
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
             .assert_stdout_eq(format!(
                 include_str!("output/list_success_with_origins_flag.out"),
                 FIXTURE1_PATH = &fixture1_path,
@@ -99,4 +143,65 @@ mod subcommand_list {
 
         Ok(())
     }
+
+    #[test]
+    fn it_should_print_the_links_each_dot_owns_if_the_links_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let fixture2_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithDirectory)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture1_path)
+            .output()?;
+        manager.cmd(BIN)?.arg("add").arg(&fixture2_path).output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("list")
+            .arg("--links")
+            .output()?
+            .assert_stderr_eq(format!(
+                "[info] Dots are installed in {}\n",
+                manager.dots_dir()
+            ))
+            .assert_stdout_eq(
+                "example_dot\n  ~/.bashrc => shell/bashrc\n  ~/.zshrc => shell/zshrc\nexample_dot_with_directory",
+            )
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_print_each_dot_as_json_if_the_json_format_is_passed() -> TestResult {
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithDirectory;
+        let manager = TestManager::new()?;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let fixture2_path = manager.setup_fixture_as_git_repo(&fixture2)?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture1_path).output()?;
+        manager.cmd(BIN)?.arg("add").arg(&fixture2_path).output()?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("list")
+            .arg("--format")
+            .arg("json")
+            .output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(format!(
+                include_str!("output/list_success_with_json_format.out"),
+                DOTS_DIR = manager.dots_dir(),
+                FIXTURE1_PATH = &fixture1_path,
+                FIXTURE2_PATH = &fixture2_path,
+            ))
+            .assert_success();
+
+        Ok(())
+    }
 }
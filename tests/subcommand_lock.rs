@@ -0,0 +1,78 @@
+mod subcommand_lock {
+    use test_utils::{
+        cargo_bin, pretty_assert, AssertableOutput, Fixture, TestManager, TestResult,
+    };
+    use utils::git;
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("lock").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/lock_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_record_the_current_commit_of_each_dot() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let commit = git::rev_parse(&fixture_path, "HEAD")?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("lock")
+            .output()?
+            .assert_stderr_eq("[info] Locked 1 dots to their current commits\n")
+            .assert_stdout_eq("")
+            .assert_success();
+
+        pretty_assert(
+            format!(
+                include_str!("locks/example_dot.lock"),
+                ORIGIN = fixture_path,
+                COMMIT = commit
+            ),
+            manager.read_lockfile()?,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_refresh_the_lockfile_after_a_dot_is_updated() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+        let commit = git::rev_parse(&fixture_path, "HEAD")?;
+
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        pretty_assert(
+            format!(
+                include_str!("locks/example_dot.lock"),
+                ORIGIN = fixture_path,
+                COMMIT = commit
+            ),
+            manager.read_lockfile()?,
+        );
+
+        Ok(())
+    }
+}
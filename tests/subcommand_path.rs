@@ -16,14 +16,60 @@ mod subcommand_path {
     }
 
     #[test]
-    fn it_should_print_usage_when_its_missing_arguments() -> TestResult {
+    fn it_should_print_the_root_if_no_dot_is_given() -> TestResult {
         let manager = TestManager::new()?;
         let output = manager.cmd(BIN)?.arg("path").output()?;
 
         output
-            .assert_stderr_eq(include_str!("output/path_fail_with_missing_dot.out"))
-            .assert_stdout_eq("")
-            .assert_fail_with_code(2);
+            .assert_stderr_eq("")
+            .assert_stdout_eq(manager.dots_dir())
+            .assert_success();
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_the_root_from_dots_home() -> TestResult {
+        let manager = TestManager::new()?;
+        let root = manager.home_dir().join("custom");
+        let output = manager
+            .cmd(BIN)?
+            .env("DOTS_HOME", &root)
+            .arg("path")
+            .output()?;
+
+        output.assert_stdout_eq(&root).assert_success();
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_prefer_the_root_flag_over_dots_home() -> TestResult {
+        let manager = TestManager::new()?;
+        let root = manager.home_dir().join("flag");
+        let output = manager
+            .cmd(BIN)?
+            .env("DOTS_HOME", manager.home_dir().join("env"))
+            .arg("path")
+            .arg("--root")
+            .arg(&root)
+            .output()?;
+
+        output.assert_stdout_eq(&root).assert_success();
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_xdg_data_home_if_there_is_no_existing_install() -> TestResult {
+        let manager = TestManager::new()?;
+        let data_home = manager.home_dir().join(".local/share");
+        let output = manager
+            .cmd(BIN)?
+            .env("XDG_DATA_HOME", &data_home)
+            .arg("path")
+            .output()?;
+
+        output
+            .assert_stdout_eq(data_home.join("dots"))
+            .assert_success();
         Ok(())
     }
 
@@ -56,4 +102,31 @@ mod subcommand_path {
             .assert_success();
         Ok(())
     }
+
+    #[test]
+    fn it_should_print_the_path_as_json_if_the_json_format_is_passed() -> TestResult {
+        let fixture = Fixture::ExampleDot;
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let root = manager.dots_dir();
+        let dot_path = root.join(fixture.name());
+
+        manager.cmd(BIN)?.arg("add").arg(&fixture_path).output()?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("path")
+            .arg(fixture.name())
+            .arg("--format")
+            .arg("json")
+            .output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(format!(
+                "{{\n  \"version\": 1,\n  \"root\": \"{root}\",\n  \"name\": \"example_dot\",\n  \"path\": \"{dot_path}\"\n}}\n"
+            ))
+            .assert_success();
+        Ok(())
+    }
 }
@@ -0,0 +1,77 @@
+mod subcommand_pull {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("pull").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/pull_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_copy_changes_back_into_the_dot() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithCopy)?;
+        let zshrc_path = manager.home_dir().join(".zshrc");
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(&zshrc_path, "local changes")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("pull")
+            .arg(&zshrc_path)
+            .output()?
+            .assert_stdout_eq("")
+            .assert_success();
+
+        assert_eq!(
+            fs::read_to_string(dot_path.join("shell/zshrc"))?,
+            "local changes"
+        );
+
+        /* the copy is no longer considered modified */
+        manager.cmd(BIN)?.arg("install").output()?.assert_success();
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_for_files_that_were_not_copied_by_dots() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithCopy)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("pull")
+            .arg(home_dir.join(".bashrc"))
+            .output()?
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        Ok(())
+    }
+}
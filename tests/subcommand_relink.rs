@@ -0,0 +1,74 @@
+mod subcommand_relink {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("relink").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/relink_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_relink_the_given_dot_and_leave_other_dots_alone() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithDirectory;
+        let fixture1_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let fixture2_path = manager.setup_fixture_as_git_repo(&fixture2)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture1_path)
+            .output()?;
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture2_path)
+            .output()?;
+        fs::remove_file(home_dir.join(".zshrc"))?;
+        fs::remove_file(home_dir.join("bin"))?;
+
+        manager
+            .cmd(BIN)?
+            .arg("relink")
+            .arg(fixture1.name())
+            .output()?
+            .assert_success();
+
+        assert_eq!(
+            home_dir.join(".zshrc").read_link()?,
+            dot_path.join("shell/zshrc")
+        );
+        assert!(!home_dir.join("bin").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_the_dot_is_not_installed() -> TestResult {
+        let manager = TestManager::new()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("relink")
+            .arg("example_dot")
+            .output()?
+            .assert_stderr_eq(
+                "[error] Unable to find an installed dot with the name: example_dot\n",
+            )
+            .assert_fail_with_code(1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+mod subcommand_restore {
+    use std::fs;
+    use test_utils::{
+        cargo_bin, pretty_assert, AssertableOutput, Fixture, TestManager, TestResult,
+    };
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("restore").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/restore_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_put_a_backed_up_file_back_in_place_of_its_symlink() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+        let bashrc_path = home_dir.join(".bashrc");
+
+        fs::write(&bashrc_path, "original bashrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .arg("--force")
+            .output()?
+            .assert_success();
+
+        assert!(bashrc_path.is_symlink());
+
+        manager
+            .cmd(BIN)?
+            .arg("restore")
+            .arg(&bashrc_path)
+            .output()?
+            .assert_stdout_eq("")
+            .assert_success();
+
+        assert!(!bashrc_path.is_symlink());
+        assert_eq!(fs::read_to_string(&bashrc_path)?, "original bashrc");
+        assert!(!manager.dots_dir().join(".backups").exists());
+
+        pretty_assert(
+            format!(
+                include_str!("footprints/example_dot_with_unlinked_file.toml"),
+                HOME = home_dir
+            ),
+            manager.read_footprint()?,
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_list_all_backups_if_no_path_is_given() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+
+        fs::write(home_dir.join(".bashrc"), "original bashrc")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .arg("--force")
+            .output()?
+            .assert_success();
+
+        let output = manager.cmd(BIN)?.arg("restore").output()?;
+        let stdout = String::from_utf8(output.stdout.clone())?;
+
+        output.assert_stderr_eq("").assert_success();
+        assert!(stdout.starts_with(&format!("{} (", home_dir.join(".bashrc"))));
+        assert_eq!(stdout.lines().count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_there_is_no_backup_for_the_given_path() -> TestResult {
+        let manager = TestManager::new()?;
+        let bashrc_path = manager.home_dir().join(".bashrc");
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("restore")
+            .arg(&bashrc_path)
+            .output()?;
+
+        output
+            .assert_stderr_eq(format!(
+                include_str!("output/restore_fail_with_missing_backup.err"),
+                PATH = bashrc_path
+            ))
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+mod subcommand_status {
+    use std::{fs, process::Command};
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+    use utils::git;
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_report_the_pinned_ref_of_each_dot() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+
+        git::tag(&fixture_path, "v1")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--tag")
+            .arg("v1")
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("status")
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq("example_dot (pinned to tag v1)\n")
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_flag_dots_that_have_drifted_from_their_pinned_ref() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let dot_path = manager.expected_dot_path(&fixture);
+
+        git::tag(&fixture_path, "v1")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .arg("--tag")
+            .arg("v1")
+            .output()?
+            .assert_success();
+
+        git::config(&dot_path, "user.name", "webdesserts")?;
+        git::config(&dot_path, "user.email", "test@webdesserts.com")?;
+        Command::new("git")
+            .args(["commit", "--allow-empty", "-m", "drift"])
+            .current_dir(&dot_path)
+            .output()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("status")
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/status_with_drifted_pin.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_flag_copies_that_were_changed_since_they_were_copied() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithCopy)?;
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(manager.home_dir().join(".zshrc"), "local changes")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("status")
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/status_with_modified_copy.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_print_each_changed_file_as_json_if_the_json_format_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let dot_path = manager.expected_dot_path(&fixture);
+
+        manager
+            .cmd(BIN)?
+            .arg("add")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::write(dot_path.join("shell/bashrc"), "# changed\n")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("status")
+            .arg("--format")
+            .arg("json")
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq(format!(
+                include_str!("output/status_with_json_format.out"),
+                DOT_PATH = &dot_path,
+            ))
+            .assert_success();
+
+        Ok(())
+    }
+}
@@ -51,6 +51,35 @@ mod subcommand_uninstall {
         Ok(())
     }
 
+    #[test]
+    fn should_remove_a_dot_whose_dot_toml_is_broken() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture = Fixture::ExampleDot;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture)?;
+        let dot_path = manager.expected_dot_path(&fixture);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(fixture_path)
+            .output()?
+            .assert_success();
+
+        std::fs::write(dot_path.join("Dot.toml"), "[package")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("uninstall")
+            .arg(fixture.name())
+            .output()?
+            .assert_success();
+
+        assert!(!dot_path.exists());
+        assert!(!manager.home_dir().join(".bashrc").is_symlink());
+
+        Ok(())
+    }
+
     #[test]
     fn should_remove_links_from_the_given_dot() -> TestResult {
         let manager = TestManager::new()?;
@@ -0,0 +1,192 @@
+mod subcommand_update {
+    use std::fs;
+    use std::process::Command;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+    use utils::git;
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("update").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/update_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_pull_new_commits_and_link_any_new_files() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        assert!(!home_dir.join(".bash_profile").exists());
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+
+        let output = manager.cmd(BIN)?.arg("update").output()?;
+
+        output
+            .assert_stderr_eq(include_str!("output/update_success.err"))
+            .assert_stdout_eq("")
+            .assert_success();
+
+        assert!(dot_path.join("shell/bash_profile").is_file());
+        assert_eq!(
+            home_dir.join(".bash_profile").read_link()?,
+            dot_path.join("shell/bash_profile")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_update_a_dot_that_was_checked_out_from_the_lockfile() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--locked")
+            .output()?
+            .assert_success();
+        assert!(!dot_path.join("shell/bash_profile").exists());
+
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+
+        assert!(dot_path.join("shell/bash_profile").is_file());
+        assert_eq!(git::get_branch(&dot_path)?, git::get_branch(&fixture_path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_switch_a_locked_dot_to_a_pinned_branch_it_was_not_cloned_with() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+        manager.cmd(BIN)?.arg("lock").output()?.assert_success();
+
+        Command::new("git")
+            .args([
+                "commit",
+                "--allow-empty",
+                "-m",
+                "move past the locked commit",
+            ])
+            .current_dir(&fixture_path)
+            .output()?;
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg("--locked")
+            .output()?
+            .assert_success();
+
+        Command::new("git")
+            .args(["checkout", "-b", "feature"])
+            .current_dir(&fixture_path)
+            .output()?;
+        manager.update_fixture(&fixture1, &fixture2)?;
+        fs::write(
+            manager.dots_dir().join("dot-pins.toml"),
+            "[example_dot]\nbranch = \"feature\"\n",
+        )?;
+
+        manager.cmd(BIN)?.arg("update").output()?.assert_success();
+
+        assert!(dot_path.join("shell/bash_profile").is_file());
+        assert_eq!(git::get_branch(&dot_path)?, "feature");
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_refuse_to_update_a_dot_with_local_changes() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture1 = Fixture::ExampleDot;
+        let fixture2 = Fixture::ExampleDotWithLinkAdded;
+        let fixture_path = manager.setup_fixture_as_git_repo(&fixture1)?;
+        let home_dir = manager.home_dir();
+        let dot_path = manager.expected_dot_path(&fixture1);
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager.update_fixture(&fixture1, &fixture2)?;
+        fs::write(dot_path.join("shell/bashrc"), "# local change")?;
+
+        let output = manager
+            .cmd(BIN)?
+            .arg("update")
+            .arg(fixture1.name())
+            .output()?;
+
+        output
+            .assert_stderr_eq(include_str!("output/update_fail_with_local_changes.err"))
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        assert!(!dot_path.join("shell/bash_profile").exists());
+        assert!(!home_dir.join(".bash_profile").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_if_the_given_dot_is_not_installed() -> TestResult {
+        let manager = TestManager::new()?;
+
+        manager
+            .cmd(BIN)?
+            .arg("update")
+            .arg("missing_dot")
+            .output()?
+            .assert_stderr_eq(
+                "[error] Unable to find an installed dot with the name: missing_dot\n",
+            )
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+mod subcommand_which {
+    use std::fs;
+    use test_utils::{cargo_bin, AssertableOutput, Fixture, TestManager, TestResult};
+
+    const BIN: &str = cargo_bin!("dots");
+
+    #[test]
+    fn it_should_print_help_text_when_the_help_flag_is_passed() -> TestResult {
+        let manager = TestManager::new()?;
+        let output = manager.cmd(BIN)?.arg("which").arg("--help").output()?;
+
+        output
+            .assert_stderr_eq("")
+            .assert_stdout_eq(include_str!("output/which_help.out"))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_find_the_dot_that_links_a_file() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("which")
+            .arg(home_dir.join(".bashrc"))
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq(format!(
+                "example_dot shell/bashrc\n  source:  {dot_path}/shell/bashrc\n  link:    {home_dir}/.bashrc (symlink)\n  tracked: yes\n  healthy: yes"
+            ))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_find_files_inside_of_linked_directories() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDotWithDirectory)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDotWithDirectory);
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("--format")
+            .arg("json")
+            .arg("which")
+            .arg(home_dir.join("bin/hello.sh"))
+            .output()?
+            .assert_stderr_eq("")
+            .assert_stdout_eq(format!(
+                "{{\n  \"version\": 1,\n  \"path\": \"{home_dir}/bin/hello.sh\",\n  \"dot\": \"example_dot_with_directory\",\n  \"src\": \"{dot_path}/bin/hello.sh\",\n  \"relative\": \"bin/hello.sh\",\n  \"dest\": \"{home_dir}/bin\",\n  \"mode\": \"symlink\",\n  \"tracked\": true,\n  \"healthy\": true,\n  \"problem\": null\n}}\n"
+            ))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_links_that_are_broken() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let dot_path = manager.expected_dot_path(&Fixture::ExampleDot);
+        let zshrc_path = manager.home_dir().join(".zshrc");
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        fs::remove_file(&zshrc_path)?;
+        fs::write(&zshrc_path, "")?;
+
+        manager
+            .cmd(BIN)?
+            .arg("which")
+            .arg(&zshrc_path)
+            .output()?
+            .assert_stdout_eq(format!(
+                "example_dot shell/zshrc\n  source:  {dot_path}/shell/zshrc\n  link:    {zshrc_path} (symlink)\n  tracked: yes\n  healthy: no, {zshrc_path} isn't linked to {dot_path}/shell/zshrc"
+            ))
+            .assert_success();
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_fail_for_paths_that_no_dot_links() -> TestResult {
+        let manager = TestManager::new()?;
+        let fixture_path = manager.setup_fixture_as_git_repo(&Fixture::ExampleDot)?;
+        let home_dir = manager.home_dir();
+
+        manager
+            .cmd(BIN)?
+            .arg("install")
+            .arg(&fixture_path)
+            .output()?
+            .assert_success();
+
+        manager
+            .cmd(BIN)?
+            .arg("which")
+            .arg(home_dir.join(".profile"))
+            .output()?
+            .assert_stdout_eq("")
+            .assert_fail_with_code(1);
+
+        Ok(())
+    }
+}